@@ -1,41 +1,481 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    sync::{Mutex, OnceLock},
+};
+
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    body::{to_bytes, Body},
+    extract::{FromRequest, OriginalUri, Path, Request, State},
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::{IntoResponse, Response},
+    Form, Json,
 };
-use sea_orm::{ActiveValue, DatabaseConnection, EntityTrait};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use http_body_util::LengthLimitError;
+use sea_orm::{ActiveValue, DatabaseConnection, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
 
 use crate::{
     config::{Config, ConfigService},
     error::Result,
-    model::heartbeat,
+    utils,
 };
 
+/// Optional heartbeat payload
+#[derive(Default, Deserialize)]
+pub struct Payload {
+    #[serde(default)]
+    pub status: PayloadStatus,
+    pub msg: Option<String>,
+    pub duration_ms: Option<i64>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PayloadStatus {
+    #[default]
+    Up,
+    Down,
+}
+
+impl<S> FromRequest<S> for Payload
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> std::result::Result<Self, Self::Rejection> {
+        let content_type = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+
+        if content_type.starts_with("application/json") {
+            Json::<Self>::from_request(req, state)
+                .await
+                .map(|Json(v)| v)
+                .map_err(IntoResponse::into_response)
+        } else if content_type.starts_with("application/x-www-form-urlencoded") {
+            Form::<Self>::from_request(req, state)
+                .await
+                .map(|Form(v)| v)
+                .map_err(IntoResponse::into_response)
+        } else {
+            // Treat plain body as message
+            let body = String::from_request(req, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            Ok(Self {
+                msg: (!body.is_empty()).then_some(body),
+                ..Default::default()
+            })
+        }
+    }
+}
+
 pub async fn handler(
     State(db): State<DatabaseConnection>,
     Path(token): Path<String>,
+    payload: Payload,
 ) -> Result<StatusCode> {
+    save_heartbeat(&db, &token, payload).await
+}
+
+#[derive(Deserialize)]
+pub struct BatchItem {
+    pub token: String,
+    pub timestamp: Option<DateTime<Utc>>,
+
+    #[serde(flatten)]
+    pub payload: Payload,
+}
+
+#[derive(Serialize)]
+pub struct BatchResult {
+    pub token: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+pub async fn batch_handler(
+    State(db): State<DatabaseConnection>,
+    Json(items): Json<Vec<BatchItem>>,
+) -> Result<Json<Vec<BatchResult>>> {
     let cfg = Config::get_instance();
+    let now = Utc::now();
 
-    // Get service name by token
-    let service = cfg.services.iter().find(|s| {
-        if let ConfigService::Http { token: target, .. } = s {
-            token == *target
-        } else {
-            false
+    // Validate items
+    let mut heartbeats = vec![];
+    let mut latest = HashMap::<String, (&ConfigService, DateTime<Utc>, &Payload)>::new();
+    let mut results = vec![];
+    for item in &items {
+        let error = match validate_item(cfg, item, now) {
+            Ok(service) => {
+                let timestamp = item.timestamp.unwrap_or(now);
+                let mut heartbeat = utils::create_heartbeat(
+                    service,
+                    matches!(item.payload.status, PayloadStatus::Up),
+                    item.payload.msg.clone(),
+                    item.payload.duration_ms,
+                );
+                heartbeat.timestamp = ActiveValue::Set(timestamp);
+                heartbeats.push(heartbeat);
+
+                // Keep newest item per service for state update
+                let key = service.get_key();
+                if latest.get(&key).is_none_or(|v| timestamp >= v.1) {
+                    latest.insert(key, (service, timestamp, &item.payload));
+                }
+                None
+            }
+            Err(err) => Some(err.to_string()),
+        };
+
+        results.push(BatchResult {
+            token: item.token.clone(),
+            success: error.is_none(),
+            error,
+        });
+    }
+
+    // Insert in one transaction
+    if !heartbeats.is_empty() {
+        let txn = db.begin().await?;
+        utils::save_heartbeats(&txn, heartbeats).await?;
+        txn.commit().await?;
+    }
+
+    // Update state after commit
+    for (service, _, payload) in latest.into_values() {
+        utils::set_heartbeat_state(
+            service,
+            matches!(payload.status, PayloadStatus::Up),
+            payload.msg.as_deref(),
+        );
+    }
+
+    // Success
+    Ok(Json(results))
+}
+
+/// Validate batch item, return its service
+fn validate_item<'a>(
+    cfg: &'a Config,
+    item: &BatchItem,
+    now: DateTime<Utc>,
+) -> std::result::Result<&'a ConfigService, &'static str> {
+    let Some(service) = cfg.find_by_token(&item.token) else {
+        return Err("token not found");
+    };
+    if item.timestamp.is_some_and(|v| v > now) {
+        return Err("timestamp in future");
+    }
+
+    Ok(service)
+}
+
+pub async fn action_handler(
+    State(db): State<DatabaseConnection>,
+    Path((token, action)): Path<(String, String)>,
+    mut payload: Payload,
+) -> Result<StatusCode> {
+    match action.as_str() {
+        "start" => {
+            let Some(service) = Config::get_instance().find_by_token(&token) else {
+                return Ok(StatusCode::NOT_FOUND);
+            };
+            get_starts()
+                .lock()
+                .unwrap()
+                .insert(service.get_key(), Utc::now());
+            return Ok(StatusCode::NO_CONTENT);
         }
+        "fail" => payload.status = PayloadStatus::Down,
+        code => match code.parse::<u8>() {
+            Ok(0) => payload.status = PayloadStatus::Up,
+            Ok(code) => {
+                payload.status = PayloadStatus::Down;
+                payload
+                    .msg
+                    .get_or_insert_with(|| format!("exit code {}", code));
+            }
+            Err(_) => return Ok(StatusCode::NOT_FOUND),
+        },
+    }
+
+    save_heartbeat(&db, &token, payload).await
+}
+
+pub async fn signed_handler(
+    State(db): State<DatabaseConnection>,
+    Path(name): Path<String>,
+    req: Request,
+) -> Result<StatusCode> {
+    let cfg = Config::get_instance();
+
+    // Get service and secret by name
+    let service = cfg.services.iter().find_map(|s| match s {
+        ConfigService::Http {
+            name: target,
+            secret: Some(secret),
+            ..
+        } if *target == name => Some((s, secret)),
+        _ => None,
     });
-    if service.is_none() {
+    let Some((service, secret)) = service else {
         return Ok(StatusCode::NOT_FOUND);
+    };
+
+    // Read request
+    let (parts, body) = req.into_parts();
+    let body = match to_bytes(body, 64 * 1024).await {
+        Ok(body) => body,
+        Err(err) if err.source().is_some_and(|v| v.is::<LengthLimitError>()) => {
+            return Ok(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+        Err(err) => return Err(err.into()),
+    };
+    let path = parts
+        .extensions
+        .get::<OriginalUri>()
+        .map_or(parts.uri.path(), |v| v.path());
+    let header = |key: &str| parts.headers.get(key).and_then(|v| v.to_str().ok());
+    let (Some(timestamp), Some(signature)) = (
+        header("X-Celestob-Timestamp").and_then(|v| v.parse::<i64>().ok()),
+        header("X-Celestob-Signature").and_then(|v| hex::decode(v).ok()),
+    ) else {
+        return Ok(StatusCode::UNAUTHORIZED);
+    };
+
+    // Verify signature
+    let signed = Signed {
+        timestamp,
+        signature,
+        method: parts.method.as_str(),
+        path,
+        body: &body,
+    };
+    let verified = signed.verify(
+        secret,
+        cfg.signature_window.as_secs() as i64,
+        Utc::now().timestamp(),
+        &mut get_signatures().lock().unwrap(),
+    );
+    if !verified {
+        return Ok(StatusCode::UNAUTHORIZED);
     }
-    let name = service.unwrap().get_name();
 
-    // Create heartbeat
-    let heartbeat = heartbeat::ActiveModel {
-        name: ActiveValue::Set(format!("http:{}", name)),
-        ..Default::default()
+    // Parse payload
+    let req = Request::from_parts(parts, Body::from(body));
+    let payload = match Payload::from_request(req, &()).await {
+        Ok(payload) => payload,
+        Err(res) => return Ok(res.status()),
+    };
+
+    save_service_heartbeat(&db, service, payload).await
+}
+
+/// Signed heartbeat request
+struct Signed<'a> {
+    timestamp: i64,
+    signature: Vec<u8>,
+    method: &'a str,
+    path: &'a str,
+    body: &'a [u8],
+}
+
+impl Signed<'_> {
+    /// Verify signature of `<timestamp>.<method>.<path>.<body>`, reject stale and replayed ones
+    fn verify(
+        &self,
+        secret: &str,
+        window: i64,
+        now: i64,
+        seen: &mut HashMap<Vec<u8>, i64>,
+    ) -> bool {
+        // Check replay window
+        if (now - self.timestamp).abs() > window {
+            return false;
+        }
+
+        // Verify signature
+        let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+            return false;
+        };
+        mac.update(format!("{}.{}.{}.", self.timestamp, self.method, self.path).as_bytes());
+        mac.update(self.body);
+        if mac.verify_slice(&self.signature).is_err() {
+            return false;
+        }
+
+        // Reject replayed signature
+        seen.retain(|_, v| now - *v <= window);
+        seen.insert(self.signature.clone(), self.timestamp)
+            .is_none()
+    }
+}
+
+/// <signature, timestamp>
+fn get_signatures() -> &'static Mutex<HashMap<Vec<u8>, i64>> {
+    static I: OnceLock<Mutex<HashMap<Vec<u8>, i64>>> = OnceLock::new();
+    I.get_or_init(Default::default)
+}
+
+/// <service name, start time>
+fn get_starts() -> &'static Mutex<HashMap<String, DateTime<Utc>>> {
+    static I: OnceLock<Mutex<HashMap<String, DateTime<Utc>>>> = OnceLock::new();
+    I.get_or_init(Default::default)
+}
+
+async fn save_heartbeat(
+    db: &DatabaseConnection,
+    token: &str,
+    payload: Payload,
+) -> Result<StatusCode> {
+    let cfg = Config::get_instance();
+
+    // Get service by token
+    let Some(service) = cfg.find_by_token(token) else {
+        return Ok(StatusCode::NOT_FOUND);
     };
-    heartbeat::Entity::insert(heartbeat).exec(&db).await?;
+
+    save_service_heartbeat(db, service, payload).await
+}
+
+async fn save_service_heartbeat(
+    db: &DatabaseConnection,
+    service: &ConfigService,
+    payload: Payload,
+) -> Result<StatusCode> {
+    // Measure runtime since start
+    let start = get_starts().lock().unwrap().remove(&service.get_key());
+    let duration = payload
+        .duration_ms
+        .or_else(|| start.map(|v| (Utc::now() - v).num_milliseconds()));
+
+    // Create heartbeat
+    let success = matches!(payload.status, PayloadStatus::Up);
+    let heartbeat = utils::create_heartbeat(service, success, payload.msg.clone(), duration);
+    utils::save_heartbeats(db, vec![heartbeat]).await?;
+    utils::set_heartbeat_state(service, success, payload.msg.as_deref());
 
     // Success
     Ok(StatusCode::NO_CONTENT)
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeDelta;
+
+    use super::*;
+
+    fn item(token: &str, timestamp: Option<DateTime<Utc>>) -> BatchItem {
+        BatchItem {
+            token: token.into(),
+            timestamp,
+            payload: Payload::default(),
+        }
+    }
+
+    fn sign(secret: &str, timestamp: i64, body: &[u8]) -> Vec<u8> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(format!("{}.POST./api/heartbeat/signed/job.", timestamp).as_bytes());
+        mac.update(body);
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    fn signed<'a>(timestamp: i64, signature: Vec<u8>, body: &'a [u8]) -> Signed<'a> {
+        Signed {
+            timestamp,
+            signature,
+            method: "POST",
+            path: "/api/heartbeat/signed/job",
+            body,
+        }
+    }
+
+    #[test]
+    fn signature_valid() {
+        let mut seen = HashMap::new();
+        let now = 1_700_000_000;
+        let body = br#"{"status":"up"}"#;
+        let signature = sign("secret", now - 10, body);
+        assert!(signed(now - 10, signature, body).verify("secret", 300, now, &mut seen));
+
+        let signature = sign("other", now, body);
+        assert!(!signed(now, signature, body).verify("secret", 300, now, &mut seen));
+    }
+
+    #[test]
+    fn signature_tampered() {
+        let mut seen = HashMap::new();
+        let now = 1_700_000_000;
+        let signature = sign("secret", now, br#"{"status":"up"}"#);
+        let body = br#"{"status":"down"}"#;
+        assert!(!signed(now, signature, body).verify("secret", 300, now, &mut seen));
+
+        let signature = sign("secret", now, body);
+        assert!(!signed(now + 1, signature, body).verify("secret", 300, now, &mut seen));
+    }
+
+    #[test]
+    fn signature_stale() {
+        let mut seen = HashMap::new();
+        let now = 1_700_000_000;
+        let body = b"";
+        let signature = sign("secret", now - 301, body);
+        assert!(!signed(now - 301, signature, body).verify("secret", 300, now, &mut seen));
+
+        let signature = sign("secret", now + 301, body);
+        assert!(!signed(now + 301, signature, body).verify("secret", 300, now, &mut seen));
+    }
+
+    #[test]
+    fn signature_replayed() {
+        let mut seen = HashMap::new();
+        let now = 1_700_000_000;
+        let body = b"";
+        let signature = sign("secret", now, body);
+        assert!(signed(now, signature.clone(), body).verify("secret", 300, now, &mut seen));
+        assert!(!signed(now, signature.clone(), body).verify("secret", 300, now + 1, &mut seen));
+
+        // Expired signatures are forgotten
+        let signature = sign("secret", now + 400, body);
+        assert!(signed(now + 400, signature, body).verify("secret", 300, now + 400, &mut seen));
+        assert_eq!(seen.len(), 1);
+    }
+
+    #[test]
+    fn batch_validation() {
+        let cfg = toml::from_str::<Config>(
+            r#"
+            db_url = "sqlite::memory:"
+
+            [[services]]
+            type = "http"
+            name = "job"
+            description = ""
+            token = "abc"
+            "#,
+        )
+        .unwrap();
+        let now = Utc::now();
+
+        let service = validate_item(&cfg, &item("abc", None), now).unwrap();
+        assert_eq!(service.get_key(), "http:job");
+        assert!(validate_item(&cfg, &item("abc", Some(now - TimeDelta::hours(1))), now).is_ok());
+        assert!(validate_item(&cfg, &item("abc", Some(now)), now).is_ok());
+        assert_eq!(
+            validate_item(&cfg, &item("abc", Some(now + TimeDelta::seconds(1))), now).err(),
+            Some("timestamp in future")
+        );
+        assert_eq!(
+            validate_item(&cfg, &item("xyz", None), now).err(),
+            Some("token not found")
+        );
+    }
+}
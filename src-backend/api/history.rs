@@ -0,0 +1,127 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    error::Result,
+    model::{event, heartbeat, status},
+};
+
+#[derive(Deserialize)]
+pub struct Range {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct History {
+    pub name: String,
+    pub events: Vec<Event>,
+    pub heartbeats: Vec<Heartbeat>,
+    pub resolution: u64,
+    pub buckets: Vec<Bucket>,
+}
+
+#[derive(Serialize)]
+pub struct Event {
+    pub timestamp: String,
+    pub kind: String,
+    pub message: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct Bucket {
+    pub timestamp: String,
+    pub count: i32,
+}
+
+#[derive(Serialize)]
+pub struct Heartbeat {
+    pub timestamp: String,
+    pub success: bool,
+    pub message: Option<String>,
+    pub duration_ms: Option<i64>,
+}
+
+pub async fn handler(
+    State(db): State<DatabaseConnection>,
+    Path(name): Path<String>,
+    Query(range): Query<Range>,
+) -> Result<Response> {
+    let cfg = Config::get_instance();
+    let now = Utc::now();
+    let to = range.to.unwrap_or(now);
+    let from = range
+        .from
+        .unwrap_or(now - Duration::from_std(cfg.retention.raw)?);
+
+    // Check service name
+    if !cfg.get_services().iter().any(|s| s.get_key() == name) {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    }
+
+    // Fetch events
+    let events = event::Entity::find()
+        .filter(event::Column::Name.eq(&name))
+        .filter(event::Column::Timestamp.between(from, to))
+        .order_by_desc(event::Column::Timestamp)
+        .all(&db)
+        .await?
+        .into_iter()
+        .map(|v| Event {
+            timestamp: v.timestamp.to_rfc3339(),
+            kind: v.kind,
+            message: v.message,
+        })
+        .collect::<Vec<_>>();
+
+    // Fetch recent heartbeats
+    let heartbeats = heartbeat::Entity::find()
+        .filter(heartbeat::Column::Name.eq(&name))
+        .filter(heartbeat::Column::Timestamp.between(from, to))
+        .order_by_desc(heartbeat::Column::Timestamp)
+        .limit(100)
+        .all(&db)
+        .await?
+        .into_iter()
+        .map(|v| Heartbeat {
+            timestamp: v.timestamp.to_rfc3339(),
+            success: v.success,
+            message: v.message,
+            duration_ms: v.duration,
+        })
+        .collect::<Vec<_>>();
+
+    // Fetch rollups of finest resolution still kept
+    let resolution = cfg.retention.pick(&from);
+    let buckets = status::Entity::find()
+        .filter(status::Column::Name.eq(&name))
+        .filter(status::Column::Resolution.eq(resolution.as_secs() as i64))
+        .filter(status::Column::Timestamp.between(from, to))
+        .order_by_asc(status::Column::Timestamp)
+        .all(&db)
+        .await?
+        .into_iter()
+        .map(|v| Bucket {
+            timestamp: v.timestamp.to_rfc3339(),
+            count: v.count,
+        })
+        .collect::<Vec<_>>();
+
+    // Success
+    Ok(Json(History {
+        name,
+        events,
+        heartbeats,
+        resolution: resolution.as_secs(),
+        buckets,
+    })
+    .into_response())
+}
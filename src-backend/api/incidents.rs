@@ -0,0 +1,75 @@
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    sea_query::Condition, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::Result, model::incident};
+
+#[derive(Deserialize)]
+pub struct Filter {
+    pub service: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct Incidents {
+    pub incidents: Vec<Incident>,
+}
+
+#[derive(Serialize)]
+pub struct Incident {
+    pub name: String,
+    pub state: String,
+    pub cause: Option<String>,
+    pub start: String,
+    pub end: Option<String>,
+    pub duration_ms: Option<i64>,
+}
+
+pub async fn handler(
+    State(db): State<DatabaseConnection>,
+    Query(filter): Query<Filter>,
+) -> Result<Json<Incidents>> {
+    // Filter incidents overlapping range
+    let mut query = incident::Entity::find();
+    if let Some(service) = filter.service {
+        query = query.filter(incident::Column::Name.eq(service));
+    }
+    if let Some(from) = filter.from {
+        query = query.filter(
+            Condition::any()
+                .add(incident::Column::End.is_null())
+                .add(incident::Column::End.gte(from)),
+        );
+    }
+    if let Some(to) = filter.to {
+        query = query.filter(incident::Column::Start.lt(to));
+    }
+
+    // Fetch incidents
+    let incidents = query
+        .order_by_desc(incident::Column::Start)
+        .limit(500)
+        .all(&db)
+        .await?
+        .into_iter()
+        .map(|v| Incident {
+            name: v.name,
+            state: v.state,
+            cause: v.cause,
+            start: v.start.to_rfc3339(),
+            end: v.end.map(|v| v.to_rfc3339()),
+            duration_ms: v.duration,
+        })
+        .collect::<Vec<_>>();
+
+    // Success
+    Ok(Json(Incidents { incidents }))
+}
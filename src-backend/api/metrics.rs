@@ -0,0 +1,60 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Duration, Utc};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder};
+use serde::Serialize;
+
+use crate::{config::Config, error::Result, model::metric};
+
+#[derive(Serialize)]
+pub struct Metrics {
+    pub name: String,
+    pub metrics: Vec<Metric>,
+}
+
+#[derive(Serialize)]
+pub struct Metric {
+    pub timestamp: String,
+    pub cpu: f64,
+    pub memory_usage: i64,
+    pub memory_limit: i64,
+    pub network_rx: i64,
+    pub network_tx: i64,
+}
+
+pub async fn handler(
+    State(db): State<DatabaseConnection>,
+    Path(name): Path<String>,
+) -> Result<Response> {
+    let cfg = Config::get_instance();
+
+    // Check service name
+    if !cfg.get_services().iter().any(|s| s.get_key() == name) {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    }
+
+    // Fetch metrics
+    let metrics = metric::Entity::find()
+        .filter(metric::Column::Name.eq(&name))
+        .filter(metric::Column::Timestamp.gte(Utc::now() - Duration::days(2)))
+        .order_by_asc(metric::Column::Timestamp)
+        .all(&db)
+        .await?
+        .into_iter()
+        .map(|v| Metric {
+            timestamp: v.timestamp.to_rfc3339(),
+            cpu: v.cpu,
+            memory_usage: v.memory_usage,
+            memory_limit: v.memory_limit,
+            network_rx: v.network_rx,
+            network_tx: v.network_tx,
+        })
+        .collect::<Vec<_>>();
+
+    // Success
+    Ok(Json(Metrics { name, metrics }).into_response())
+}
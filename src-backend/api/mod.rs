@@ -1,5 +1,16 @@
 mod heartbeat;
+mod history;
+mod incidents;
+mod metrics;
 mod status;
+mod uptime;
 
+pub use heartbeat::action_handler as heartbeat_action;
+pub use heartbeat::batch_handler as heartbeat_batch;
 pub use heartbeat::handler as heartbeat;
+pub use heartbeat::signed_handler as heartbeat_signed;
+pub use history::handler as history;
+pub use incidents::handler as incidents;
+pub use metrics::handler as metrics;
 pub use status::handler as status;
+pub use uptime::handler as uptime;
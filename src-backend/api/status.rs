@@ -1,15 +1,13 @@
 use std::collections::HashMap;
 
 use axum::{extract::State, Json};
-use chrono::{DateTime, Duration, Timelike, Utc};
+use chrono::{DateTime, Duration, Utc};
 use itertools::Itertools;
 use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
 use serde::Serialize;
 
 use crate::{
-    config::{Config, ConfigService},
-    error::Result,
-    model::status,
+    config::Config, error::Result, model::status, schedule::Schedule, state, uptime, utils,
 };
 
 #[derive(Serialize)]
@@ -22,84 +20,177 @@ pub struct Response {
 pub struct Service {
     pub name: String,
     pub desc: String,
+    pub pivot: String,
+    pub bucket: u64,
+    pub window: u64,
     pub stat: Vec<Option<i32>>,
+    pub expected: Vec<Option<i32>>,
+    pub classification: Vec<Option<state::State>>,
+    pub latency: Vec<Option<Latency>>,
+    pub state: state::State,
+    pub cert_days: Option<i64>,
+    pub cert_verified: Option<bool>,
+    pub uptime: uptime::Periods,
+}
+
+/// Latencies of bucket in milliseconds
+#[derive(Serialize)]
+pub struct Latency {
+    pub min: i64,
+    pub avg: i64,
+    pub p50: i64,
+    pub p95: i64,
+    pub p99: i64,
+}
+
+impl Latency {
+    fn from_status(v: &status::Model) -> Option<Self> {
+        Some(Self {
+            min: v.latency_min?,
+            avg: v.latency_avg?,
+            p50: v.latency_p50?,
+            p95: v.latency_p95?,
+            p99: v.latency_p99?,
+        })
+    }
 }
 
 pub async fn handler(State(db): State<DatabaseConnection>) -> Result<Json<Response>> {
     let cfg = Config::get_instance();
+    let services = cfg.get_services();
+    let now = Utc::now();
 
-    // Fetch status
-    let result = status::Entity::find()
-        .filter(status::Column::Timestamp.gte(Utc::now() - Duration::days(2)))
-        .all(&db)
-        .await?
-        .into_iter()
-        .into_group_map_by(|v| v.name.clone());
+    // Fetch status of each bucket size within its longest window
+    let mut result = HashMap::new();
+    for (bucket, group) in services.iter().into_group_map_by(|s| cfg.get_bucket(s)) {
+        let window = group.iter().map(|s| cfg.get_window(s)).max().unwrap();
+        let names = group.iter().map(|s| s.get_key()).collect::<Vec<_>>();
+        let rows = status::Entity::find()
+            .filter(status::Column::Name.is_in(names))
+            .filter(status::Column::Resolution.eq(bucket.as_secs() as i64))
+            .filter(status::Column::Timestamp.gte(now - Duration::from_std(window)?))
+            .all(&db)
+            .await?;
+        result.extend(
+            rows.into_iter()
+                .into_group_map_by(|v| (v.name.clone(), v.resolution)),
+        );
+    }
 
     // Get pivot
-    let pivot = Utc::now()
-        .with_minute(0)
-        .unwrap()
-        .with_second(0)
-        .unwrap()
-        .with_nanosecond(0)
-        .unwrap();
-
-    // Compute stats
-    fn compute_stat(v: &Vec<status::Model>, pivot: &DateTime<Utc>) -> Vec<Option<i32>> {
+    let pivot = utils::truncate(&now, cfg.bucket);
+
+    // Compute buckets
+    fn compute_buckets<'a>(
+        v: &'a [status::Model],
+        pivot: &DateTime<Utc>,
+        bucket: Duration,
+        len: i32,
+    ) -> Vec<Option<&'a status::Model>> {
         let tmp = v
-            .clone()
-            .into_iter()
-            .map(|v| (v.timestamp, v.count))
+            .iter()
+            .map(|v| (v.timestamp, v))
             .collect::<HashMap<_, _>>();
 
-        let mut tmp = (0..48)
-            .into_iter()
-            .map(|i| {
-                tmp.get(
-                    &(*pivot - Duration::hours(i))
-                        .format("%Y-%m-%dT%H:00:00%:z")
-                        .to_string(),
-                )
-                .map(|v| v.clone())
-            })
+        let mut tmp = (0..len)
+            .map(|i| tmp.get(&(*pivot - bucket * i)).copied())
             .collect::<Vec<_>>();
-        while tmp.len() > 0 && tmp.last().unwrap().is_none() {
+        while !tmp.is_empty() && tmp.last().unwrap().is_none() {
             tmp.pop();
         }
 
         tmp
     }
 
+    // Compute expected counts
+    fn compute_expected(
+        len: usize,
+        schedule: Option<Schedule>,
+        pivot: &DateTime<Utc>,
+        bucket: Duration,
+    ) -> Vec<Option<i32>> {
+        let now = Utc::now();
+        (0..len as i32)
+            .map(|i| {
+                let start = *pivot - bucket * i;
+                let end = (start + bucket).min(now);
+                schedule.as_ref().map(|v| v.count(start, end))
+            })
+            .collect()
+    }
+
+    // Classify buckets by ratio of received to expected beats
+    fn compute_classification(
+        stat: &[Option<i32>],
+        expected: &[Option<i32>],
+        (up, degraded): (f64, f64),
+    ) -> Vec<Option<state::State>> {
+        stat.iter()
+            .zip(expected)
+            .map(|(count, expected)| {
+                let ratio = match (count, expected) {
+                    (None, _) => return None,
+                    (Some(count), Some(expected)) if *expected > 0 => {
+                        *count as f64 / *expected as f64
+                    }
+                    (Some(0), None) => 0.0,
+                    _ => 1.0,
+                };
+                Some(if ratio >= up {
+                    state::State::Up
+                } else if ratio >= degraded {
+                    state::State::Degraded
+                } else {
+                    state::State::Down
+                })
+            })
+            .collect()
+    }
+
+    // Compute uptime of all services at once
+    let uptime = uptime::periods_of(&db, &services).await?;
+
     // Compute return services
-    let services = cfg
-        .services
-        .iter()
-        .map(|s| match s {
-            ConfigService::Http {
-                name, description, ..
-            } => Service {
-                name: format!("http:{}", name),
-                desc: description.clone(),
-                stat: result
-                    .get(&format!("http:{}", name))
-                    .map_or(vec![], |v| compute_stat(v, &pivot)),
-            },
-            ConfigService::Docker {
-                name, description, ..
-            } => Service {
-                name: format!("docker:{}", name),
-                desc: description.clone(),
-                stat: result
-                    .get(&format!("docker:{}", name))
-                    .map_or(vec![], |v| compute_stat(v, &pivot)),
-            },
-        })
-        .collect::<Vec<_>>();
+    let mut ret = vec![];
+    for (s, uptime) in services.iter().zip(uptime) {
+        let health = state::get(&s.get_key());
+        let bucket = cfg.get_bucket(s);
+        let window = cfg.get_window(s).as_secs();
+        let len = (window / bucket.as_secs()) as i32;
+        let pivot = utils::truncate(&now, bucket);
+        let bucket = Duration::from_std(bucket).unwrap();
+        let buckets = result
+            .get(&(s.get_key(), bucket.num_seconds()))
+            .map_or(vec![], |v| compute_buckets(v, &pivot, bucket, len));
+        let stat = buckets
+            .iter()
+            .map(|v| v.map(|v| v.count))
+            .collect::<Vec<_>>();
+        let expected = compute_expected(stat.len(), s.get_schedule(), &pivot, bucket);
+        let latency = buckets
+            .iter()
+            .map(|v| v.and_then(Latency::from_status))
+            .collect();
+        ret.push(Service {
+            name: s.get_key(),
+            desc: s.get_description().clone(),
+            pivot: pivot.to_rfc3339(),
+            bucket: bucket.num_seconds() as u64,
+            window,
+            classification: compute_classification(&stat, &expected, cfg.get_thresholds(s)),
+            expected,
+            stat,
+            latency,
+            state: health.state,
+            cert_days: health.cert_days,
+            cert_verified: health.cert_verified,
+            uptime,
+        });
+    }
 
     // Success
     Ok(Json(Response {
         pivot: pivot.to_rfc3339(),
-        services,
+        services: ret,
     }))
 }
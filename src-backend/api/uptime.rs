@@ -0,0 +1,73 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use sea_orm::DatabaseConnection;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    error::Result,
+    uptime::{self, Periods},
+};
+
+#[derive(Deserialize)]
+pub struct Range {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct Uptime {
+    pub name: String,
+    pub periods: Periods,
+    pub range: Option<RangeUptime>,
+}
+
+#[derive(Serialize)]
+pub struct RangeUptime {
+    pub from: String,
+    pub to: String,
+    pub uptime: Option<f64>,
+}
+
+pub async fn handler(
+    State(db): State<DatabaseConnection>,
+    Path(name): Path<String>,
+    Query(range): Query<Range>,
+) -> Result<Response> {
+    let cfg = Config::get_instance();
+
+    // Get service by name
+    let services = cfg.get_services();
+    let Some(service) = services.iter().find(|s| s.get_key() == name) else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+
+    // Compute custom range
+    let range = match range.from {
+        Some(from) => {
+            let to = range.to.unwrap_or_else(Utc::now);
+            if from >= to {
+                return Ok(StatusCode::BAD_REQUEST.into_response());
+            }
+            Some(RangeUptime {
+                from: from.to_rfc3339(),
+                to: to.to_rfc3339(),
+                uptime: uptime::compute(&db, service, from, to).await?,
+            })
+        }
+        None => None,
+    };
+
+    // Success
+    Ok(Json(Uptime {
+        name,
+        periods: uptime::periods(&db, service).await?,
+        range,
+    })
+    .into_response())
+}
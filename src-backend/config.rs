@@ -1,7 +1,20 @@
-use std::{collections::HashSet, fs, sync::OnceLock};
+use std::{
+    collections::HashSet,
+    fs,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{OnceLock, RwLock},
+    time::Duration,
+};
 
 use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use reqwest::Method;
 use serde::Deserialize;
+use tracing::warn;
+
+use crate::{schedule::Schedule, task};
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
@@ -11,11 +24,44 @@ pub struct Config {
     #[serde(default = "Config::default_bind_port")]
     pub bind_port: u16,
 
+    #[serde(default = "Config::default_bind_addr")]
+    pub udp_bind_addr: String,
+
+    pub udp_bind_port: Option<u16>,
+    pub udp_secret: Option<String>,
+
+    #[serde(default = "Config::default_udp_window", with = "humantime_serde")]
+    pub udp_window: Duration,
+
     pub db_url: String,
     pub secret: Option<String>,
 
+    #[serde(default = "Config::default_signature_window", with = "humantime_serde")]
+    pub signature_window: Duration,
+
+    #[serde(default = "Config::default_bucket", with = "humantime_serde")]
+    pub bucket: Duration,
+
+    #[serde(default = "Config::default_window", with = "humantime_serde")]
+    pub window: Duration,
+
+    #[serde(default = "Config::default_up_threshold")]
+    pub up_threshold: f64,
+
+    #[serde(default = "Config::default_degraded_threshold")]
+    pub degraded_threshold: f64,
+
+    #[serde(default)]
+    pub retention: ConfigRetention,
+
+    #[serde(default)]
+    pub dockers: Vec<ConfigDocker>,
+
     #[serde(default)]
     pub services: Vec<ConfigService>,
+
+    #[serde(skip)]
+    discovered: RwLock<Vec<ConfigService>>,
 }
 
 impl Config {
@@ -30,25 +76,245 @@ impl Config {
         })
     }
 
+    /// Get configured and discovered services
+    pub fn get_services(&self) -> Vec<ConfigService> {
+        let mut services = self.services.clone();
+        services.extend(self.discovered.read().unwrap().iter().cloned());
+        services
+    }
+
+    /// Find push service by heartbeat token
+    pub fn find_by_token(&self, token: &str) -> Option<&ConfigService> {
+        self.services.iter().find(|s| {
+            if let ConfigService::Http { token: target, .. } = s {
+                target.as_deref() == Some(token)
+            } else {
+                false
+            }
+        })
+    }
+
+    /// Get status bucket size of service
+    pub fn get_bucket(&self, service: &ConfigService) -> Duration {
+        service.get_view().bucket.unwrap_or(self.bucket)
+    }
+
+    /// Get status history window of service
+    pub fn get_window(&self, service: &ConfigService) -> Duration {
+        service.get_view().window.unwrap_or(self.window)
+    }
+
+    /// Get `(up, degraded)` thresholds of service as ratios of expected beats
+    pub fn get_thresholds(&self, service: &ConfigService) -> (f64, f64) {
+        let view = service.get_view();
+        (
+            view.up_threshold.unwrap_or(self.up_threshold),
+            view.degraded_threshold.unwrap_or(self.degraded_threshold),
+        )
+    }
+
+    /// Check whether service is paused by key
+    pub fn is_paused(&self, key: &str) -> bool {
+        let discovered = self.discovered.read().unwrap();
+        self.services
+            .iter()
+            .chain(discovered.iter())
+            .any(|s| s.get_view().paused && s.get_key() == key)
+    }
+
+    /// Get status resolutions of service, its bucket and rollups
+    pub fn get_resolutions(&self, service: &ConfigService) -> Vec<Duration> {
+        let mut resolutions = vec![self.get_bucket(service)];
+        for v in [ConfigRetention::HOURLY, ConfigRetention::DAILY] {
+            if !resolutions.contains(&v) {
+                resolutions.push(v);
+            }
+        }
+        resolutions
+    }
+
+    /// Replace discovered services of docker endpoint, skip those conflicting with others
+    pub fn set_discovered(&self, endpoint: &str, services: Vec<ConfigService>) {
+        let mut discovered = self.discovered.write().unwrap();
+        discovered.retain(|s| s.get_endpoint().map(String::as_str) != Some(endpoint));
+
+        let mut merged = self.services.clone();
+        merged.extend(discovered.iter().cloned());
+        for s in services {
+            // Configured container takes precedence
+            if let ConfigService::Docker {
+                container,
+                endpoint,
+                ..
+            } = &s
+            {
+                let configured = self.services.iter().any(|v| {
+                    v.get_endpoint() == Some(endpoint)
+                        && matches!(v, ConfigService::Docker { container: target, .. } if target == container)
+                });
+                if configured {
+                    continue;
+                }
+            }
+
+            merged.push(s);
+            if let Err(err) = Self::verify_services(&merged) {
+                warn!(
+                    "discovered service skipped: name={}, err={}",
+                    merged.pop().unwrap().get_key(),
+                    err
+                );
+                continue;
+            }
+            discovered.push(merged.last().unwrap().clone());
+        }
+    }
+
     fn verify(&self) -> Result<()> {
         let mut set = HashSet::new();
+        for d in &self.dockers {
+            if !set.insert(d.name.as_str()) {
+                bail!("docker `{}` duplicated", d.name);
+            }
+            let tls = [&d.ca, &d.cert, &d.key].map(Option::is_some);
+            if tls.contains(&true) && tls.contains(&false) {
+                bail!(
+                    "docker `{}` requires all of ca, cert and key for TLS",
+                    d.name
+                );
+            }
+        }
+        if set.is_empty() {
+            set.insert("default");
+        }
+
         for s in &self.services {
-            if let ConfigService::Http { token, .. } = s {
-                if !set.insert(token.clone()) {
-                    bail!("token `{}` duplicated", token);
+            if let Some(endpoint) = s.get_endpoint() {
+                if !set.contains(endpoint.as_str()) {
+                    bail!("docker `{}` not found", endpoint);
                 }
             }
         }
 
-        set.clear();
+        if self.retention.raw < Duration::from_secs(2 * 86400) {
+            bail!("raw retention shorter than 2d");
+        }
+
+        Self::verify_view(self.bucket, self.window)?;
         for s in &self.services {
-            if let ConfigService::Docker { container, .. } = s {
-                if !set.insert(container.clone()) {
+            let view = s.get_view();
+            let bucket = view.bucket.unwrap_or(self.bucket);
+            let window = view.window.unwrap_or(self.window);
+            if let Err(err) = Self::verify_view(bucket, window) {
+                bail!("service `{}` invalid: {}", s.get_key(), err);
+            }
+
+            let up = view.up_threshold.unwrap_or(self.up_threshold);
+            let degraded = view.degraded_threshold.unwrap_or(self.degraded_threshold);
+            if !(0.0..=1.0).contains(&degraded) || !(degraded..=1.0).contains(&up) {
+                bail!("service `{}` has invalid thresholds", s.get_key());
+            }
+        }
+
+        Self::verify_services(&self.services)
+    }
+
+    fn verify_view(bucket: Duration, window: Duration) -> Result<()> {
+        if bucket < Duration::from_secs(60) {
+            bail!("bucket `{:?}` shorter than 1m", bucket);
+        }
+        if window < bucket {
+            bail!("window `{:?}` shorter than bucket", window);
+        }
+        if window.as_secs() / bucket.as_secs() > 1000 {
+            bail!("window `{:?}` exceeds 1000 buckets", window);
+        }
+
+        Ok(())
+    }
+
+    fn verify_services(services: &[ConfigService]) -> Result<()> {
+        let mut set = HashSet::new();
+        for s in services {
+            if !set.insert(s.get_key()) {
+                bail!("service `{}` duplicated", s.get_key());
+            }
+        }
+
+        set.clear();
+        for s in services {
+            if let ConfigService::Http { token, secret, .. } = s {
+                if token.is_none() && secret.is_none() {
+                    bail!("service `{}` has neither token nor secret", s.get_key());
+                }
+                if let Some(token) = token {
+                    if !set.insert(token.clone()) {
+                        bail!("token `{}` duplicated", token);
+                    }
+                }
+            }
+        }
+
+        set.clear();
+        for s in services {
+            if let ConfigService::Docker {
+                container,
+                endpoint,
+                ..
+            } = s
+            {
+                if !set.insert(format!("{}:{}", endpoint, container)) {
                     bail!("container `{}` duplicated", container);
                 }
             }
         }
 
+        for s in services {
+            if let ConfigService::Probe { method, .. } = s {
+                if Method::from_bytes(method.as_bytes()).is_err() {
+                    bail!("method `{}` invalid", method);
+                }
+            }
+        }
+
+        for s in services {
+            let interval = match s {
+                ConfigService::Http { interval, .. } => *interval,
+                _ => s.get_interval(),
+            };
+            if interval.is_some_and(|v| v.is_zero()) {
+                bail!("service `{}` has zero interval", s.get_key());
+            }
+            if s.get_timeout().is_some_and(|v| v.is_zero()) {
+                bail!("service `{}` has zero timeout", s.get_key());
+            }
+        }
+
+        for s in services {
+            if let ConfigService::Http { interval, cron, .. } = s {
+                if interval.is_some() && cron.is_some() {
+                    bail!("service `{}` has both interval and cron", s.get_key());
+                }
+                if let Some(cron) = cron {
+                    if let Err(err) = Schedule::cron(cron) {
+                        bail!("cron `{}` invalid: {}", cron, err);
+                    }
+                }
+            }
+        }
+
+        for s in services {
+            if let ConfigService::Dns {
+                pattern: Some(pattern),
+                ..
+            } = s
+            {
+                if let Err(err) = Regex::new(pattern) {
+                    bail!("pattern `{}` invalid: {}", pattern, err);
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -58,20 +324,218 @@ impl Config {
     fn default_bind_port() -> u16 {
         3000
     }
+    fn default_up_threshold() -> f64 {
+        0.9
+    }
+    fn default_degraded_threshold() -> f64 {
+        0.5
+    }
+    fn default_bucket() -> Duration {
+        Duration::from_secs(3600)
+    }
+    fn default_window() -> Duration {
+        Duration::from_secs(48 * 3600)
+    }
+    fn default_signature_window() -> Duration {
+        Duration::from_secs(300)
+    }
+    fn default_udp_window() -> Duration {
+        Duration::from_secs(60)
+    }
 }
 
 #[derive(Debug, Deserialize)]
+pub struct ConfigRetention {
+    #[serde(default = "ConfigRetention::default_raw", with = "humantime_serde")]
+    pub raw: Duration,
+
+    #[serde(default = "ConfigRetention::default_hourly", with = "humantime_serde")]
+    pub hourly: Duration,
+
+    #[serde(default = "ConfigRetention::default_daily", with = "humantime_serde")]
+    pub daily: Duration,
+}
+
+impl ConfigRetention {
+    /// Resolution of hourly rollups
+    pub const HOURLY: Duration = Duration::from_secs(3600);
+
+    /// Resolution of daily rollups
+    pub const DAILY: Duration = Duration::from_secs(86400);
+
+    /// Get retention of status in resolution, `None` if not a rollup
+    pub fn get(&self, resolution: Duration) -> Option<Duration> {
+        match resolution {
+            Self::HOURLY => Some(self.hourly),
+            Self::DAILY => Some(self.daily),
+            _ => None,
+        }
+    }
+
+    /// Pick the finest rollup resolution still covering time
+    pub fn pick(&self, time: &DateTime<Utc>) -> Duration {
+        if *time >= Utc::now() - self.hourly {
+            Self::HOURLY
+        } else {
+            Self::DAILY
+        }
+    }
+
+    fn default_raw() -> Duration {
+        Duration::from_secs(3 * 86400)
+    }
+    fn default_hourly() -> Duration {
+        Duration::from_secs(90 * 86400)
+    }
+    fn default_daily() -> Duration {
+        Duration::from_secs(730 * 86400)
+    }
+}
+
+impl Default for ConfigRetention {
+    fn default() -> Self {
+        Self {
+            raw: Self::default_raw(),
+            hourly: Self::default_hourly(),
+            daily: Self::default_daily(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfigDocker {
+    pub name: String,
+    pub url: String,
+    pub ca: Option<PathBuf>,
+    pub cert: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum ConfigService {
     Http {
         name: String,
         description: String,
-        token: String,
+
+        #[serde(flatten)]
+        view: ConfigView,
+
+        token: Option<String>,
+        secret: Option<String>,
+
+        #[serde(default, with = "humantime_serde")]
+        interval: Option<Duration>,
+
+        /// Cron expression in UTC, with optional seconds field
+        cron: Option<String>,
+
+        #[serde(default = "ConfigService::default_grace", with = "humantime_serde")]
+        grace: Duration,
     },
     Docker {
         name: String,
         description: String,
+
+        #[serde(flatten)]
+        view: ConfigView,
+
         container: String,
+
+        #[serde(default = "ConfigService::default_endpoint")]
+        endpoint: String,
+
+        #[serde(default = "ConfigService::default_healthcheck")]
+        healthcheck: bool,
+    },
+    #[serde(alias = "url")]
+    Probe {
+        name: String,
+        description: String,
+
+        #[serde(flatten)]
+        view: ConfigView,
+
+        url: String,
+
+        #[serde(default = "ConfigService::default_method")]
+        method: String,
+
+        #[serde(default)]
+        expected_status: Vec<u16>,
+
+        #[serde(default = "ConfigService::default_timeout", with = "humantime_serde")]
+        timeout: Duration,
+
+        #[serde(default = "ConfigService::default_interval", with = "humantime_serde")]
+        interval: Duration,
+    },
+    Tcp {
+        name: String,
+        description: String,
+
+        #[serde(flatten)]
+        view: ConfigView,
+
+        host: String,
+        port: u16,
+
+        #[serde(default = "ConfigService::default_timeout", with = "humantime_serde")]
+        timeout: Duration,
+
+        #[serde(default = "ConfigService::default_interval", with = "humantime_serde")]
+        interval: Duration,
+    },
+    Tls {
+        name: String,
+        description: String,
+
+        #[serde(flatten)]
+        view: ConfigView,
+
+        host: String,
+
+        #[serde(default = "ConfigService::default_tls_port")]
+        port: u16,
+
+        server_name: Option<String>,
+
+        #[serde(default = "ConfigService::default_warn_days")]
+        warn_days: i64,
+
+        #[serde(default)]
+        critical_days: i64,
+
+        #[serde(default = "ConfigService::default_verify")]
+        verify: bool,
+
+        #[serde(default = "ConfigService::default_timeout", with = "humantime_serde")]
+        timeout: Duration,
+
+        #[serde(default = "ConfigService::default_interval", with = "humantime_serde")]
+        interval: Duration,
+    },
+    Dns {
+        name: String,
+        description: String,
+
+        #[serde(flatten)]
+        view: ConfigView,
+
+        query: String,
+
+        #[serde(default)]
+        record_type: ConfigRecordType,
+
+        resolver: SocketAddr,
+        expected: Option<String>,
+        pattern: Option<String>,
+
+        #[serde(default = "ConfigService::default_timeout", with = "humantime_serde")]
+        timeout: Duration,
+
+        #[serde(default = "ConfigService::default_interval", with = "humantime_serde")]
+        interval: Duration,
     },
 }
 
@@ -80,6 +544,145 @@ impl ConfigService {
         match self {
             Self::Http { name, .. } => name,
             Self::Docker { name, .. } => name,
+            Self::Probe { name, .. } => name,
+            Self::Tcp { name, .. } => name,
+            Self::Tls { name, .. } => name,
+            Self::Dns { name, .. } => name,
+        }
+    }
+
+    pub fn get_description(&self) -> &String {
+        match self {
+            Self::Http { description, .. } => description,
+            Self::Docker { description, .. } => description,
+            Self::Probe { description, .. } => description,
+            Self::Tcp { description, .. } => description,
+            Self::Tls { description, .. } => description,
+            Self::Dns { description, .. } => description,
+        }
+    }
+
+    pub fn get_view(&self) -> &ConfigView {
+        match self {
+            Self::Http { view, .. } => view,
+            Self::Docker { view, .. } => view,
+            Self::Probe { view, .. } => view,
+            Self::Tcp { view, .. } => view,
+            Self::Tls { view, .. } => view,
+            Self::Dns { view, .. } => view,
+        }
+    }
+
+    /// Get heartbeat name of service, e.g. `http:<name>`
+    pub fn get_key(&self) -> String {
+        match self {
+            Self::Http { name, .. } => format!("http:{}", name),
+            Self::Docker { name, .. } => format!("docker:{}", name),
+            Self::Probe { name, .. } => format!("probe:{}", name),
+            Self::Tcp { name, .. } => format!("tcp:{}", name),
+            Self::Tls { name, .. } => format!("tls:{}", name),
+            Self::Dns { name, .. } => format!("dns:{}", name),
+        }
+    }
+
+    /// Get docker endpoint name of container service
+    pub fn get_endpoint(&self) -> Option<&String> {
+        match self {
+            Self::Docker { endpoint, .. } => Some(endpoint),
+            _ => None,
+        }
+    }
+
+    /// Get expected beat schedule of service
+    pub fn get_schedule(&self) -> Option<Schedule> {
+        match self {
+            Self::Http {
+                interval: Some(interval),
+                ..
+            } => Some(Schedule::interval(*interval)),
+            Self::Http {
+                cron: Some(cron), ..
+            } => Schedule::cron(cron).ok(),
+            Self::Http { .. } => None,
+            Self::Docker { .. } => Some(Schedule::interval(task::CHECK_CONTAINERS_INTERVAL)),
+            _ => self.get_interval().map(Schedule::interval),
         }
     }
+
+    /// Get check interval of actively probed service
+    pub fn get_interval(&self) -> Option<Duration> {
+        match self {
+            Self::Probe { interval, .. } => Some(*interval),
+            Self::Tcp { interval, .. } => Some(*interval),
+            Self::Tls { interval, .. } => Some(*interval),
+            Self::Dns { interval, .. } => Some(*interval),
+            _ => None,
+        }
+    }
+
+    /// Get timeout of actively probed service
+    pub fn get_timeout(&self) -> Option<Duration> {
+        match self {
+            Self::Probe { timeout, .. } => Some(*timeout),
+            Self::Tcp { timeout, .. } => Some(*timeout),
+            Self::Tls { timeout, .. } => Some(*timeout),
+            Self::Dns { timeout, .. } => Some(*timeout),
+            _ => None,
+        }
+    }
+
+    fn default_grace() -> Duration {
+        Duration::from_secs(60)
+    }
+    fn default_method() -> String {
+        "GET".into()
+    }
+    fn default_timeout() -> Duration {
+        Duration::from_secs(10)
+    }
+    fn default_interval() -> Duration {
+        Duration::from_secs(60)
+    }
+    fn default_endpoint() -> String {
+        "default".into()
+    }
+    fn default_healthcheck() -> bool {
+        true
+    }
+    fn default_verify() -> bool {
+        true
+    }
+    fn default_tls_port() -> u16 {
+        443
+    }
+    fn default_warn_days() -> i64 {
+        14
+    }
+}
+
+/// Per service overrides of status view
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ConfigView {
+    #[serde(default)]
+    pub paused: bool,
+
+    pub up_threshold: Option<f64>,
+    pub degraded_threshold: Option<f64>,
+
+    #[serde(default, with = "humantime_serde")]
+    pub bucket: Option<Duration>,
+
+    #[serde(default, with = "humantime_serde")]
+    pub window: Option<Duration>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ConfigRecordType {
+    #[default]
+    A,
+    Aaaa,
+    Cname,
+    Txt,
+    Mx,
 }
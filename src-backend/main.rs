@@ -2,11 +2,19 @@ mod api;
 mod config;
 mod error;
 mod middleware;
+mod migration;
 mod model;
+mod probe;
+mod schedule;
+mod state;
 mod task;
+mod udp;
+mod uptime;
 mod utils;
 
-use anyhow::Result;
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
 use axum::{
     routing::{get, post},
     Router,
@@ -14,11 +22,16 @@ use axum::{
 use axum_response_cache::CacheLayer;
 use bollard::Docker;
 use config::Config;
+use migration::Migrator;
 use mimalloc::MiMalloc;
 use sea_orm::Database;
-use tokio::{net::TcpListener, signal};
+use sea_orm_migration::MigratorTrait;
+use tokio::{
+    net::{TcpListener, UdpSocket},
+    signal,
+};
 use tower_http::{compression::CompressionLayer, services::ServeDir, trace::TraceLayer};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 #[global_allocator]
@@ -43,21 +56,90 @@ async fn main() -> Result<()> {
     let db = Database::connect(&cfg.db_url).await?;
     db.ping().await?;
 
-    utils::init_table(&db, model::heartbeat::Entity).await?;
-    utils::init_table(&db, model::status::Entity).await?;
+    // Run `migrate` subcommand
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.first().map(String::as_str) == Some("migrate") {
+        return migration::run_cli(&db, &args[1..]).await;
+    }
 
-    // Initialize docker client
-    let docker = Docker::connect_with_local_defaults()?;
-    docker.ping().await?;
+    // Apply pending migrations
+    Migrator::up(&db, None).await?;
+
+    // Initialize docker clients
+    let mut dockers = HashMap::new();
+    if cfg.dockers.is_empty() {
+        match Docker::connect_with_local_defaults() {
+            Ok(docker) => {
+                dockers.insert("default".to_string(), docker);
+            }
+            Err(err) if cfg.services.iter().any(|s| s.get_endpoint().is_some()) => {
+                return Err(err).context("fail to connect docker endpoint `default`");
+            }
+            Err(err) => warn!("docker endpoint unavailable: name=default, err={}", err),
+        }
+    }
+    for d in &cfg.dockers {
+        // Bad endpoint config would hide its services forever
+        let docker = utils::connect_docker(d)
+            .with_context(|| format!("fail to connect docker endpoint `{}`", d.name))?;
+        dockers.insert(d.name.clone(), docker);
+    }
+    for (name, docker) in &dockers {
+        if let Err(err) = docker.ping().await {
+            warn!("docker endpoint unavailable: name={}, err={}", name, err);
+        }
+    }
+
+    // Track state changes, paused services start paused
+    tokio::spawn(task::record_incidents(&db, state::subscribe()));
+    for s in &cfg.services {
+        if s.get_view().paused {
+            state::set(s.get_key(), Default::default());
+        }
+    }
 
     // Initialize scheduled tasks
     tokio::spawn(task::clean_database(&db));
-    tokio::spawn(task::check_containers(&db, &docker));
+    tokio::spawn(task::check_containers(&db, &dockers));
+    for (name, docker) in &dockers {
+        tokio::spawn(task::watch_containers(&db, name, docker));
+    }
     tokio::spawn(task::collect_status(&db));
+    tokio::spawn(task::check_deadlines(&db));
+    for s in &cfg.services {
+        if s.get_interval().is_some() {
+            tokio::spawn(task::check_probe(&db, s));
+        }
+    }
+
+    // Start UDP listening
+    if let Some(port) = cfg.udp_bind_port {
+        let socket = UdpSocket::bind((cfg.udp_bind_addr.as_str(), port)).await?;
+        tokio::spawn(udp::serve(db.clone(), socket));
+    }
 
     // Initialize routers
     let api_router = Router::new()
-        .route("/heartbeat/{token}", post(api::heartbeat))
+        .route("/heartbeat", post(api::heartbeat_batch))
+        .route(
+            "/heartbeat/{token}",
+            get(api::heartbeat).post(api::heartbeat),
+        )
+        .route("/heartbeat/signed/{name}", post(api::heartbeat_signed))
+        .route(
+            "/heartbeat/{token}/{action}",
+            get(api::heartbeat_action).post(api::heartbeat_action),
+        )
+        .route("/history/{name}", get(api::history))
+        .route("/incidents", get(api::incidents))
+        .route(
+            "/metrics/{name}",
+            get(api::metrics).layer(CacheLayer::with_lifespan(300).add_response_headers()),
+        )
+        .route(
+            "/services/{name}/uptime",
+            get(api::uptime).layer(CacheLayer::with_lifespan(300).add_response_headers()),
+        )
         .route(
             "/status",
             get(api::status).layer(CacheLayer::with_lifespan(300).add_response_headers()),
@@ -0,0 +1,101 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create heartbeats table
+        manager
+            .create_table(
+                Table::create()
+                    .table(Heartbeats::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Heartbeats::Uuid)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Heartbeats::Timestamp)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Heartbeats::Name).string().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-heartbeats-timestamp")
+                    .table(Heartbeats::Table)
+                    .col(Heartbeats::Timestamp)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-heartbeats-name")
+                    .table(Heartbeats::Table)
+                    .col(Heartbeats::Name)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create status table
+        manager
+            .create_table(
+                Table::create()
+                    .table(Status::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Status::Uuid).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Status::Timestamp).string().not_null())
+                    .col(ColumnDef::new(Status::Name).string().not_null())
+                    .col(ColumnDef::new(Status::Count).integer().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-status-timestamp")
+                    .table(Status::Table)
+                    .col(Status::Timestamp)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Status::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Heartbeats::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Heartbeats {
+    Table,
+    Uuid,
+    Timestamp,
+    Name,
+}
+
+#[derive(DeriveIden)]
+enum Status {
+    Table,
+    Uuid,
+    Timestamp,
+    Name,
+    Count,
+}
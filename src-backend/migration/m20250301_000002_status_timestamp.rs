@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use sea_orm::{ActiveValue, ConnectionTrait, DatabaseBackend, EntityTrait, Statement};
+use sea_orm_migration::prelude::*;
+use uuid::Uuid;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Check legacy column
+        match column_type(manager, "status", "timestamp").await? {
+            Some(t) if !t.contains("timestamp") => {
+                convert(manager).await?;
+            }
+            _ => {}
+        }
+
+        // Create unique index
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-status-name-timestamp")
+                    .table(Status::Table)
+                    .col(Status::Name)
+                    .col(Status::Timestamp)
+                    .unique()
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    /// Timestamps stay typed, only the unique index is dropped
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-status-name-timestamp")
+                    .table(Status::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Legacy status table with string timestamps
+mod legacy {
+    use sea_orm::entity::prelude::*;
+
+    #[derive(Clone, Debug, DeriveEntityModel)]
+    #[sea_orm(table_name = "status_legacy")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub uuid: Uuid,
+
+        pub timestamp: String,
+        pub name: String,
+        pub count: i32,
+    }
+
+    #[derive(Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+/// Status table as of this migration
+mod typed {
+    use sea_orm::entity::prelude::*;
+
+    #[derive(Clone, Debug, DeriveEntityModel)]
+    #[sea_orm(table_name = "status")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub uuid: Uuid,
+
+        pub timestamp: DateTimeUtc,
+        pub name: String,
+        pub count: i32,
+    }
+
+    #[derive(Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+/// Get column type of table, `None` if not exists
+async fn column_type(
+    manager: &SchemaManager<'_>,
+    table: &str,
+    column: &str,
+) -> Result<Option<String>, DbErr> {
+    let db = manager.get_connection();
+    let backend = db.get_database_backend();
+    let sql = match backend {
+        DatabaseBackend::Sqlite => "SELECT type AS t FROM pragma_table_info(?) WHERE name = ?",
+        DatabaseBackend::Postgres => {
+            "SELECT data_type AS t FROM information_schema.columns \
+             WHERE table_schema = current_schema() AND table_name = $1 AND column_name = $2"
+        }
+        DatabaseBackend::MySql => {
+            "SELECT data_type AS t FROM information_schema.columns \
+             WHERE table_schema = DATABASE() AND table_name = ? AND column_name = ?"
+        }
+    };
+
+    let row = db
+        .query_one(Statement::from_sql_and_values(
+            backend,
+            sql,
+            [table.into(), column.into()],
+        ))
+        .await?;
+    match row {
+        Some(row) => Ok(Some(row.try_get::<String>("", "t")?.to_lowercase())),
+        None => Ok(None),
+    }
+}
+
+/// Convert string timestamps of status table into typed timestamps
+async fn convert(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+    let db = manager.get_connection();
+
+    // Move legacy table aside and create new one
+    manager
+        .drop_index(
+            Index::drop()
+                .name("idx-status-timestamp")
+                .table(Status::Table)
+                .to_owned(),
+        )
+        .await?;
+    manager
+        .rename_table(
+            Table::rename()
+                .table(Status::Table, legacy::Entity)
+                .to_owned(),
+        )
+        .await?;
+    manager
+        .create_table(
+            Table::create()
+                .table(Status::Table)
+                .col(ColumnDef::new(Status::Uuid).uuid().not_null().primary_key())
+                .col(
+                    ColumnDef::new(Status::Timestamp)
+                        .timestamp_with_time_zone()
+                        .not_null(),
+                )
+                .col(ColumnDef::new(Status::Name).string().not_null())
+                .col(ColumnDef::new(Status::Count).integer().not_null())
+                .to_owned(),
+        )
+        .await?;
+    manager
+        .create_index(
+            Index::create()
+                .name("idx-status-timestamp")
+                .table(Status::Table)
+                .col(Status::Timestamp)
+                .to_owned(),
+        )
+        .await?;
+
+    // <(name, timestamp), (uuid, count)>
+    let mut rows = HashMap::<(String, DateTime<Utc>), (Uuid, i32)>::new();
+    for v in legacy::Entity::find().all(db).await? {
+        let timestamp = DateTime::parse_from_rfc3339(&v.timestamp)
+            .map_err(|err| DbErr::Custom(err.to_string()))?
+            .to_utc();
+        let row = rows.entry((v.name, timestamp)).or_insert((v.uuid, 0));
+        row.1 = row.1.max(v.count);
+    }
+
+    // Copy rows
+    for ((name, timestamp), (uuid, count)) in rows {
+        let status = typed::ActiveModel {
+            uuid: ActiveValue::Set(uuid),
+            timestamp: ActiveValue::Set(timestamp),
+            name: ActiveValue::Set(name),
+            count: ActiveValue::Set(count),
+        };
+        typed::Entity::insert(status).exec(db).await?;
+    }
+
+    // Drop legacy table
+    manager
+        .drop_table(Table::drop().table(legacy::Entity).to_owned())
+        .await
+}
+
+#[derive(DeriveIden)]
+enum Status {
+    Table,
+    Uuid,
+    Timestamp,
+    Name,
+    Count,
+}
@@ -0,0 +1,74 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let mut columns = vec![];
+        if !manager.has_column("heartbeats", "success").await? {
+            columns.push(
+                ColumnDef::new(Heartbeats::Success)
+                    .boolean()
+                    .not_null()
+                    .default(true)
+                    .to_owned(),
+            );
+        }
+        if !manager.has_column("heartbeats", "message").await? {
+            columns.push(ColumnDef::new(Heartbeats::Message).text().null().to_owned());
+        }
+        if !manager.has_column("heartbeats", "duration").await? {
+            columns.push(
+                ColumnDef::new(Heartbeats::Duration)
+                    .big_integer()
+                    .null()
+                    .to_owned(),
+            );
+        }
+
+        // SQLite only supports one column per statement
+        for column in columns {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Heartbeats::Table)
+                        .add_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // Success
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            Heartbeats::Duration,
+            Heartbeats::Message,
+            Heartbeats::Success,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Heartbeats::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // Success
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Heartbeats {
+    Table,
+    Success,
+    Message,
+    Duration,
+}
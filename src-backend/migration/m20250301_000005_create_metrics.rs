@@ -0,0 +1,82 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Metrics::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Metrics::Uuid)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Metrics::Timestamp)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Metrics::Name).string().not_null())
+                    .col(ColumnDef::new(Metrics::Cpu).double().not_null())
+                    .col(
+                        ColumnDef::new(Metrics::MemoryUsage)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Metrics::MemoryLimit)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Metrics::NetworkRx).big_integer().not_null())
+                    .col(ColumnDef::new(Metrics::NetworkTx).big_integer().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-metrics-timestamp")
+                    .table(Metrics::Table)
+                    .col(Metrics::Timestamp)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-metrics-name")
+                    .table(Metrics::Table)
+                    .col(Metrics::Name)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Metrics::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Metrics {
+    Table,
+    Uuid,
+    Timestamp,
+    Name,
+    Cpu,
+    MemoryUsage,
+    MemoryLimit,
+    NetworkRx,
+    NetworkTx,
+}
@@ -0,0 +1,94 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Existing rows are hourly
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Status::Table)
+                    .add_column(
+                        ColumnDef::new(Status::Resolution)
+                            .big_integer()
+                            .not_null()
+                            .default(3600),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Replace unique index
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-status-name-timestamp")
+                    .table(Status::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-status-name-resolution-timestamp")
+                    .table(Status::Table)
+                    .col(Status::Name)
+                    .col(Status::Resolution)
+                    .col(Status::Timestamp)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx-status-name-resolution-timestamp")
+                    .table(Status::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Keep hourly rows only
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(Status::Table)
+                    .and_where(Expr::col(Status::Resolution).ne(3600))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Status::Table)
+                    .drop_column(Status::Resolution)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-status-name-timestamp")
+                    .table(Status::Table)
+                    .col(Status::Name)
+                    .col(Status::Timestamp)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Status {
+    Table,
+    Name,
+    Timestamp,
+    Resolution,
+}
@@ -0,0 +1,76 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Incidents::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Incidents::Uuid)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Incidents::Name).string().not_null())
+                    .col(ColumnDef::new(Incidents::State).string().not_null())
+                    .col(ColumnDef::new(Incidents::Cause).text().null())
+                    .col(
+                        ColumnDef::new(Incidents::Start)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(Incidents::End)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .col(ColumnDef::new(Incidents::Duration).big_integer().null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-incidents-name")
+                    .table(Incidents::Table)
+                    .col(Incidents::Name)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-incidents-start")
+                    .table(Incidents::Table)
+                    .col(Incidents::Start)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Incidents::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Incidents {
+    Table,
+    Uuid,
+    Name,
+    State,
+    Cause,
+    Start,
+    End,
+    Duration,
+}
@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only supports one column per statement
+        for column in Status::LATENCIES {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Status::Table)
+                        .add_column(ColumnDef::new(column).big_integer().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // Success
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in Status::LATENCIES {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Status::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // Success
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, DeriveIden)]
+enum Status {
+    Table,
+    LatencyMin,
+    LatencyAvg,
+    LatencyP50,
+    LatencyP95,
+    LatencyP99,
+}
+
+impl Status {
+    const LATENCIES: [Self; 5] = [
+        Self::LatencyMin,
+        Self::LatencyAvg,
+        Self::LatencyP50,
+        Self::LatencyP95,
+        Self::LatencyP99,
+    ];
+}
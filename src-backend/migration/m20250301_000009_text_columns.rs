@@ -0,0 +1,60 @@
+use sea_orm::DatabaseBackend;
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite does not enforce string lengths
+        if manager.get_database_backend() == DatabaseBackend::Sqlite {
+            return Ok(());
+        }
+
+        let columns = [
+            (
+                Heartbeats::Table.into_iden(),
+                Heartbeats::Message.into_iden(),
+            ),
+            (Events::Table.into_iden(), Events::Message.into_iden()),
+            (Incidents::Table.into_iden(), Incidents::Cause.into_iden()),
+        ];
+        for (table, column) in columns {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(table)
+                        .modify_column(ColumnDef::new(column).text().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        // Success
+        Ok(())
+    }
+
+    /// Columns stay text, shortening them may truncate messages
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Heartbeats {
+    Table,
+    Message,
+}
+
+#[derive(DeriveIden)]
+enum Events {
+    Table,
+    Message,
+}
+
+#[derive(DeriveIden)]
+enum Incidents {
+    Table,
+    Cause,
+}
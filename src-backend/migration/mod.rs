@@ -0,0 +1,193 @@
+mod m20250301_000001_create_tables;
+mod m20250301_000002_status_timestamp;
+mod m20250301_000003_heartbeat_payload;
+mod m20250301_000004_create_events;
+mod m20250301_000005_create_metrics;
+mod m20250301_000006_status_resolution;
+mod m20250301_000007_create_incidents;
+mod m20250301_000008_status_latency;
+mod m20250301_000009_text_columns;
+
+use anyhow::{bail, Result};
+use sea_orm::DatabaseConnection;
+use sea_orm_migration::{async_trait::async_trait, MigrationTrait, MigratorTrait};
+
+pub struct Migrator;
+
+#[async_trait]
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![
+            Box::new(m20250301_000001_create_tables::Migration),
+            Box::new(m20250301_000002_status_timestamp::Migration),
+            Box::new(m20250301_000003_heartbeat_payload::Migration),
+            Box::new(m20250301_000004_create_events::Migration),
+            Box::new(m20250301_000005_create_metrics::Migration),
+            Box::new(m20250301_000006_status_resolution::Migration),
+            Box::new(m20250301_000007_create_incidents::Migration),
+            Box::new(m20250301_000008_status_latency::Migration),
+            Box::new(m20250301_000009_text_columns::Migration),
+        ]
+    }
+}
+
+/// Run `migrate [up|down|status|fresh|refresh|reset] [steps]` subcommand
+pub async fn run_cli(db: &DatabaseConnection, args: &[String]) -> Result<()> {
+    let steps = args.get(1).map(|v| v.parse::<u32>()).transpose()?;
+    match args.first().map_or("up", String::as_str) {
+        "up" => Migrator::up(db, steps).await?,
+        "down" => Migrator::down(db, Some(steps.unwrap_or(1))).await?,
+        "status" => Migrator::status(db).await?,
+        "fresh" => Migrator::fresh(db).await?,
+        "refresh" => Migrator::refresh(db).await?,
+        "reset" => Migrator::reset(db).await?,
+        cmd => bail!("unknown migrate command `{}`", cmd),
+    }
+
+    // Success
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use sea_orm::{
+        ConnectionTrait, Database, DatabaseBackend, EntityTrait, QueryOrder, Statement, Value,
+    };
+    use sea_orm_migration::SchemaManager;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::model::status;
+
+    const TABLES: [&str; 5] = ["heartbeats", "status", "events", "metrics", "incidents"];
+
+    async fn sqlite() -> DatabaseConnection {
+        Database::connect("sqlite::memory:").await.unwrap()
+    }
+
+    async fn has_tables(db: &DatabaseConnection) -> Vec<bool> {
+        let manager = SchemaManager::new(db);
+        let mut tables = vec![];
+        for table in TABLES {
+            tables.push(manager.has_table(table).await.unwrap());
+        }
+        tables
+    }
+
+    async fn pending(db: &DatabaseConnection) -> usize {
+        Migrator::get_pending_migrations(db).await.unwrap().len()
+    }
+
+    async fn up_down_fresh(db: &DatabaseConnection) {
+        Migrator::up(db, None).await.unwrap();
+        assert_eq!(pending(db).await, 0);
+        assert_eq!(has_tables(db).await, [true; 5]);
+
+        Migrator::down(db, None).await.unwrap();
+        assert_eq!(pending(db).await, Migrator::migrations().len());
+        assert_eq!(has_tables(db).await, [false; 5]);
+
+        Migrator::up(db, None).await.unwrap();
+        Migrator::fresh(db).await.unwrap();
+        assert_eq!(pending(db).await, 0);
+        assert_eq!(has_tables(db).await, [true; 5]);
+    }
+
+    async fn status_conversion(db: &DatabaseConnection) {
+        Migrator::fresh(db).await.unwrap();
+        Migrator::reset(db).await.unwrap();
+        Migrator::up(db, Some(1)).await.unwrap();
+
+        // Seed string timestamps, including one bucket in two offsets
+        let backend = db.get_database_backend();
+        let sql = match backend {
+            DatabaseBackend::Postgres => {
+                r#"INSERT INTO "status" ("uuid", "timestamp", "name", "count") VALUES ($1, $2, $3, $4)"#
+            }
+            _ => "INSERT INTO `status` (`uuid`, `timestamp`, `name`, `count`) VALUES (?, ?, ?, ?)",
+        };
+        for (timestamp, name, count) in [
+            ("2025-03-01T00:00:00Z", "http:job", 3),
+            ("2025-03-01T08:00:00+08:00", "http:job", 5),
+            ("2025-03-01T01:00:00+00:00", "http:job", 2),
+            ("2025-03-01T00:00:00Z", "tcp:web", 7),
+        ] {
+            let values: [Value; 4] = [
+                Uuid::new_v4().into(),
+                timestamp.into(),
+                name.into(),
+                count.into(),
+            ];
+            db.execute(Statement::from_sql_and_values(backend, sql, values))
+                .await
+                .unwrap();
+        }
+
+        Migrator::up(db, None).await.unwrap();
+        let rows = status::Entity::find()
+            .order_by_asc(status::Column::Name)
+            .order_by_asc(status::Column::Timestamp)
+            .all(db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|v| (v.name, v.timestamp, v.count, v.resolution))
+            .collect::<Vec<_>>();
+        let time = |h| Utc.with_ymd_and_hms(2025, 3, 1, h, 0, 0).unwrap();
+        assert_eq!(
+            rows,
+            [
+                ("http:job".to_string(), time(0), 5, 3600),
+                ("http:job".to_string(), time(1), 2, 3600),
+                ("tcp:web".to_string(), time(0), 7, 3600),
+            ]
+        );
+    }
+
+    async fn cli(db: &DatabaseConnection) {
+        let args = |v: &[&str]| v.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        let total = Migrator::migrations().len();
+
+        Migrator::fresh(db).await.unwrap();
+        run_cli(db, &args(&["status"])).await.unwrap();
+        run_cli(db, &args(&["down"])).await.unwrap();
+        assert_eq!(pending(db).await, 1);
+        run_cli(db, &args(&["down", "2"])).await.unwrap();
+        assert_eq!(pending(db).await, 3);
+        run_cli(db, &args(&["status"])).await.unwrap();
+        run_cli(db, &args(&[])).await.unwrap();
+        assert_eq!(pending(db).await, 0);
+        run_cli(db, &args(&["reset"])).await.unwrap();
+        assert_eq!(pending(db).await, total);
+
+        assert!(run_cli(db, &args(&["sideways"])).await.is_err());
+        assert!(run_cli(db, &args(&["down", "x"])).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn sqlite_up_down_fresh() {
+        up_down_fresh(&sqlite().await).await;
+    }
+
+    #[tokio::test]
+    async fn sqlite_status_conversion() {
+        status_conversion(&sqlite().await).await;
+    }
+
+    #[tokio::test]
+    async fn sqlite_cli() {
+        cli(&sqlite().await).await;
+    }
+
+    /// Run with `TEST_DATABASE_URL=postgres://postgres@localhost/celestob cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore = "requires TEST_DATABASE_URL"]
+    async fn external_database() {
+        let url = std::env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL not set");
+        let db = Database::connect(url).await.unwrap();
+        up_down_fresh(&db).await;
+        status_conversion(&db).await;
+        cli(&db).await;
+    }
+}
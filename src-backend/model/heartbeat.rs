@@ -12,6 +12,12 @@ pub struct Model {
 
     #[sea_orm(indexed)]
     pub name: String,
+
+    #[sea_orm(default_value = true)]
+    pub success: bool,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub message: Option<String>,
+    pub duration: Option<i64>,
 }
 
 #[derive(Debug, EnumIter, DeriveRelation)]
@@ -22,6 +28,9 @@ impl ActiveModelBehavior for ActiveModel {
         Self {
             uuid: ActiveValue::Set(Uuid::new_v4()),
             timestamp: ActiveValue::Set(Utc::now()),
+            success: ActiveValue::Set(true),
+            message: ActiveValue::Set(None),
+            duration: ActiveValue::Set(None),
             ..ActiveModelTrait::default()
         }
     }
@@ -0,0 +1,39 @@
+use chrono::Utc;
+use sea_orm::{entity::prelude::*, ActiveValue};
+
+#[derive(Clone, Debug, DeriveEntityModel)]
+#[sea_orm(table_name = "incidents")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub uuid: Uuid,
+
+    #[sea_orm(indexed)]
+    pub name: String,
+
+    pub state: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub cause: Option<String>,
+
+    #[sea_orm(indexed)]
+    pub start: DateTimeUtc,
+
+    pub end: Option<DateTimeUtc>,
+
+    /// Duration in milliseconds, set when resolved
+    pub duration: Option<i64>,
+}
+
+#[derive(Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            uuid: ActiveValue::Set(Uuid::new_v4()),
+            start: ActiveValue::Set(Utc::now()),
+            end: ActiveValue::Set(None),
+            duration: ActiveValue::Set(None),
+            ..ActiveModelTrait::default()
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use chrono::Utc;
+use sea_orm::{entity::prelude::*, ActiveValue};
+
+#[derive(Clone, Debug, DeriveEntityModel)]
+#[sea_orm(table_name = "metrics")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub uuid: Uuid,
+
+    #[sea_orm(indexed)]
+    pub timestamp: DateTimeUtc,
+
+    #[sea_orm(indexed)]
+    pub name: String,
+
+    pub cpu: f64,
+    pub memory_usage: i64,
+    pub memory_limit: i64,
+    pub network_rx: i64,
+    pub network_tx: i64,
+}
+
+#[derive(Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            uuid: ActiveValue::Set(Uuid::new_v4()),
+            timestamp: ActiveValue::Set(Utc::now()),
+            ..ActiveModelTrait::default()
+        }
+    }
+}
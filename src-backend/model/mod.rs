@@ -1,2 +1,5 @@
+pub mod event;
 pub mod heartbeat;
+pub mod incident;
+pub mod metric;
 pub mod status;
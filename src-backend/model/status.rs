@@ -1,4 +1,3 @@
-use chrono::Utc;
 use sea_orm::{entity::prelude::*, ActiveValue};
 
 #[derive(Clone, Debug, DeriveEntityModel)]
@@ -8,10 +7,20 @@ pub struct Model {
     pub uuid: Uuid,
 
     #[sea_orm(indexed)]
-    pub timestamp: String,
+    pub timestamp: DateTimeUtc,
 
     pub name: String,
     pub count: i32,
+
+    /// Bucket size in seconds
+    pub resolution: i64,
+
+    /// Latencies of successful heartbeats in milliseconds
+    pub latency_min: Option<i64>,
+    pub latency_avg: Option<i64>,
+    pub latency_p50: Option<i64>,
+    pub latency_p95: Option<i64>,
+    pub latency_p99: Option<i64>,
 }
 
 #[derive(Debug, EnumIter, DeriveRelation)]
@@ -21,7 +30,11 @@ impl ActiveModelBehavior for ActiveModel {
     fn new() -> Self {
         Self {
             uuid: ActiveValue::Set(Uuid::new_v4()),
-            timestamp: ActiveValue::Set(Utc::now().format("%Y-%m-%dT%H:00:00%:z").to_string()),
+            latency_min: ActiveValue::Set(None),
+            latency_avg: ActiveValue::Set(None),
+            latency_p50: ActiveValue::Set(None),
+            latency_p95: ActiveValue::Set(None),
+            latency_p99: ActiveValue::Set(None),
             ..ActiveModelTrait::default()
         }
     }
@@ -0,0 +1,457 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Utc;
+use hickory_resolver::{
+    config::{NameServerConfigGroup, ResolverConfig, ResolverOpts},
+    proto::rr::RecordType,
+    TokioAsyncResolver,
+};
+use regex::Regex;
+use reqwest::{Client, Method};
+use tokio::{net::TcpStream, time};
+use tokio_rustls::{
+    rustls::{
+        self,
+        client::{
+            danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+            WebPkiServerVerifier,
+        },
+        crypto::{ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider},
+        pki_types::{CertificateDer, ServerName, UnixTime},
+        ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+    },
+    TlsConnector,
+};
+use x509_parser::prelude::*;
+
+use crate::{
+    config::{ConfigRecordType, ConfigService},
+    state::{Health, State},
+};
+
+/// Probe service once
+pub async fn check(service: &ConfigService) -> Health {
+    let result = match service {
+        ConfigService::Probe {
+            url,
+            method,
+            expected_status,
+            timeout,
+            ..
+        } => http(url, method, expected_status, *timeout).await,
+        ConfigService::Tcp {
+            host,
+            port,
+            timeout,
+            ..
+        } => tcp(host, *port, *timeout).await,
+        ConfigService::Tls {
+            host,
+            port,
+            server_name,
+            warn_days,
+            critical_days,
+            verify,
+            timeout,
+            ..
+        } => {
+            let server_name = server_name.as_ref().unwrap_or(host);
+            tls(host, *port, server_name, *timeout)
+                .await
+                .map(|(days, verified)| {
+                    let mut health = if days <= *critical_days {
+                        Health::down(format!("certificate expires in {} days", days))
+                    } else if days <= *warn_days {
+                        Health::degraded(format!("certificate expires in {} days", days))
+                    } else {
+                        Health::up()
+                    };
+                    if let Err(err) = &verified {
+                        if *verify && health.state != State::Down {
+                            health = Health::down(format!("certificate not trusted: {}", err));
+                        }
+                    }
+                    health.cert_days = Some(days);
+                    health.cert_verified = Some(verified.is_ok());
+                    health
+                })
+        }
+        ConfigService::Dns {
+            query,
+            record_type,
+            resolver,
+            expected,
+            pattern,
+            timeout,
+            ..
+        } => {
+            static CACHE: OnceLock<Mutex<HashMap<String, Arc<Dns>>>> = OnceLock::new();
+            let dns = CACHE
+                .get_or_init(Default::default)
+                .lock()
+                .unwrap()
+                .entry(service.get_key())
+                .or_insert_with(|| {
+                    Arc::new(Dns::new(
+                        *record_type,
+                        *resolver,
+                        expected.as_deref(),
+                        pattern.as_deref(),
+                        *timeout,
+                    ))
+                })
+                .clone();
+            dns.check(query).await
+        }
+        _ => Err(anyhow!("service `{}` is not active", service.get_key())),
+    };
+
+    result.unwrap_or_else(Health::down)
+}
+
+async fn http(
+    url: &str,
+    method: &str,
+    expected_status: &[u16],
+    timeout: Duration,
+) -> Result<Health> {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    let client = CLIENT.get_or_init(Client::new);
+
+    // Send request
+    let method = Method::from_bytes(method.as_bytes())?;
+    let code = client
+        .request(method, url)
+        .timeout(timeout)
+        .send()
+        .await?
+        .status();
+
+    // Check status code
+    let expected = if expected_status.is_empty() {
+        code.is_success()
+    } else {
+        expected_status.contains(&code.as_u16())
+    };
+    if !expected {
+        bail!("unexpected status `{}`", code);
+    }
+
+    // Success
+    Ok(Health::up())
+}
+
+async fn tcp(host: &str, port: u16, timeout: Duration) -> Result<Health> {
+    time::timeout(timeout, TcpStream::connect((host, port))).await??;
+
+    // Success
+    Ok(Health::up())
+}
+
+/// Accept any certificate chain, leaving verification to be reported separately
+#[derive(Debug)]
+struct AcceptAnyCert(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCert {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+/// Connect to TLS server, return days remaining of leaf certificate and chain verification result
+async fn tls(
+    host: &str,
+    port: u16,
+    server_name: &str,
+    timeout: Duration,
+) -> Result<(i64, std::result::Result<(), rustls::Error>)> {
+    static CONNECTOR: OnceLock<(TlsConnector, Arc<WebPkiServerVerifier>)> = OnceLock::new();
+    let (connector, verifier) = match CONNECTOR.get() {
+        Some(v) => v,
+        None => {
+            let provider = Arc::new(ring::default_provider());
+            let roots = RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+            let verifier =
+                WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+                    .build()?;
+            let config = ClientConfig::builder_with_provider(provider.clone())
+                .with_safe_default_protocol_versions()?
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(AcceptAnyCert(provider)))
+                .with_no_client_auth();
+            CONNECTOR.get_or_init(|| (TlsConnector::from(Arc::new(config)), verifier))
+        }
+    };
+
+    // Handshake
+    let server_name = ServerName::try_from(server_name.to_string())?;
+    let stream = time::timeout(timeout, async {
+        let stream = TcpStream::connect((host, port)).await?;
+        connector.connect(server_name.clone(), stream).await
+    })
+    .await??;
+
+    // Parse leaf certificate
+    let (_, conn) = stream.get_ref();
+    let chain = conn.peer_certificates().context("no peer certificate")?;
+    let (leaf, intermediates) = chain.split_first().context("no peer certificate")?;
+    let (_, cert) = X509Certificate::from_der(leaf)?;
+    let expire = cert.validity().not_after.timestamp();
+
+    // Verify chain against webpki roots
+    let verified = verifier
+        .verify_server_cert(leaf, intermediates, &server_name, &[], UnixTime::now())
+        .map(|_| ());
+
+    // Success
+    Ok(((expire - Utc::now().timestamp()) / 86400, verified))
+}
+
+/// DNS resolver and answer matchers, built once per service
+struct Dns {
+    resolver: TokioAsyncResolver,
+    record_type: RecordType,
+    expected: Option<String>,
+    pattern: Option<Regex>,
+}
+
+impl Dns {
+    fn new(
+        record_type: ConfigRecordType,
+        resolver: SocketAddr,
+        expected: Option<&str>,
+        pattern: Option<&str>,
+        timeout: Duration,
+    ) -> Self {
+        let record_type = match record_type {
+            ConfigRecordType::A => RecordType::A,
+            ConfigRecordType::Aaaa => RecordType::AAAA,
+            ConfigRecordType::Cname => RecordType::CNAME,
+            ConfigRecordType::Txt => RecordType::TXT,
+            ConfigRecordType::Mx => RecordType::MX,
+        };
+
+        // Create resolver
+        let nameservers =
+            NameServerConfigGroup::from_ips_clear(&[resolver.ip()], resolver.port(), true);
+        let mut opts = ResolverOpts::default();
+        opts.timeout = timeout;
+        opts.attempts = 1;
+        opts.cache_size = 0;
+        let resolver =
+            TokioAsyncResolver::tokio(ResolverConfig::from_parts(None, vec![], nameservers), opts);
+
+        Self {
+            resolver,
+            record_type,
+            expected: expected.map(|v| v.trim_end_matches('.').to_string()),
+            // Pattern is validated in config
+            pattern: pattern.and_then(|v| Regex::new(v).ok()),
+        }
+    }
+
+    async fn check(&self, query: &str) -> Result<Health> {
+        // Query records
+        let answers = self
+            .resolver
+            .lookup(query, self.record_type)
+            .await?
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>();
+        if answers.is_empty() {
+            bail!("no record found");
+        }
+
+        // Check answers
+        if let Some(expected) = &self.expected {
+            if !answers.iter().any(|v| v.trim_end_matches('.') == expected) {
+                bail!("unexpected answers `{}`", answers.join(", "));
+            }
+        }
+        if let Some(pattern) = &self.pattern {
+            if !answers.iter().any(|v| pattern.is_match(v)) {
+                bail!("unexpected answers `{}`", answers.join(", "));
+            }
+        }
+
+        // Success
+        Ok(Health::up())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::Ipv4Addr, str::FromStr};
+
+    use hickory_resolver::proto::{
+        op::{Message, MessageType, ResponseCode},
+        rr::{
+            rdata::{A, MX, TXT},
+            Name, RData, Record,
+        },
+    };
+    use tokio::net::UdpSocket;
+
+    use super::*;
+
+    /// Serve fixed A/TXT/MX answers on a local UDP socket
+    async fn stub() -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0; 512];
+            loop {
+                let (len, peer) = socket.recv_from(&mut buf).await.unwrap();
+                let request = Message::from_vec(&buf[..len]).unwrap();
+                let query = request.queries()[0].clone();
+                let name = query.name().clone();
+                let rdata = match query.query_type() {
+                    RecordType::A => Some(RData::A(A(Ipv4Addr::new(192, 0, 2, 1)))),
+                    RecordType::TXT => Some(RData::TXT(TXT::new(vec!["v=spf1 -all".into()]))),
+                    RecordType::MX => Some(RData::MX(MX::new(
+                        10,
+                        Name::from_str("mail.example.com.").unwrap(),
+                    ))),
+                    _ => None,
+                };
+
+                let mut response = Message::new();
+                response
+                    .set_id(request.id())
+                    .set_message_type(MessageType::Response)
+                    .set_op_code(request.op_code())
+                    .set_recursion_desired(true)
+                    .set_recursion_available(true)
+                    .add_query(query);
+                match rdata {
+                    Some(rdata) => {
+                        response.add_answer(Record::from_rdata(name, 60, rdata));
+                    }
+                    None => {
+                        response.set_response_code(ResponseCode::NXDomain);
+                    }
+                }
+                socket
+                    .send_to(&response.to_vec().unwrap(), peer)
+                    .await
+                    .unwrap();
+            }
+        });
+        addr
+    }
+
+    async fn check(
+        resolver: SocketAddr,
+        record_type: ConfigRecordType,
+        expected: Option<&str>,
+        pattern: Option<&str>,
+    ) -> Result<Health> {
+        Dns::new(
+            record_type,
+            resolver,
+            expected,
+            pattern,
+            Duration::from_secs(2),
+        )
+        .check("example.com.")
+        .await
+    }
+
+    #[tokio::test]
+    async fn dns_expected() {
+        let resolver = stub().await;
+        assert!(
+            check(resolver, ConfigRecordType::A, Some("192.0.2.1"), None)
+                .await
+                .is_ok()
+        );
+        assert!(
+            check(resolver, ConfigRecordType::Txt, Some("v=spf1 -all"), None)
+                .await
+                .is_ok()
+        );
+        assert!(check(
+            resolver,
+            ConfigRecordType::Mx,
+            Some("10 mail.example.com"),
+            None
+        )
+        .await
+        .is_ok());
+        assert!(
+            check(resolver, ConfigRecordType::A, Some("192.0.2.2"), None)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn dns_pattern() {
+        let resolver = stub().await;
+        assert!(
+            check(resolver, ConfigRecordType::A, None, Some(r"^192\.0\.2\."))
+                .await
+                .is_ok()
+        );
+        assert!(check(resolver, ConfigRecordType::Txt, None, Some("spf1"))
+            .await
+            .is_ok());
+        assert!(
+            check(resolver, ConfigRecordType::Mx, None, Some(r"mail\.example"))
+                .await
+                .is_ok()
+        );
+        assert!(check(resolver, ConfigRecordType::Txt, None, Some("dkim"))
+            .await
+            .is_err());
+        assert!(check(resolver, ConfigRecordType::Aaaa, None, None)
+            .await
+            .is_err());
+    }
+}
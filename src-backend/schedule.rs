@@ -0,0 +1,83 @@
+use std::{str::FromStr, time::Duration};
+
+use anyhow::Result;
+use chrono::{DateTime, TimeDelta, Utc};
+
+/// Expected beat schedule of a service
+pub enum Schedule {
+    Interval(Duration),
+    Cron(Box<cron::Schedule>),
+}
+
+impl Schedule {
+    pub fn interval(interval: Duration) -> Self {
+        Self::Interval(interval)
+    }
+
+    /// Parse cron expression evaluated in UTC, the seconds field is optional
+    pub fn cron(expr: &str) -> Result<Self> {
+        let expr = if expr.split_whitespace().count() == 5 {
+            format!("0 {}", expr)
+        } else {
+            expr.to_string()
+        };
+        Ok(Self::Cron(Box::new(cron::Schedule::from_str(&expr)?)))
+    }
+
+    /// Count expected beats in `[start, end)`
+    pub fn count(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> i32 {
+        match self {
+            Self::Interval(interval) => {
+                let interval = interval.as_millis().max(1) as i64;
+                let start = start.timestamp_millis().div_euclid(interval)
+                    + (start.timestamp_millis().rem_euclid(interval) > 0) as i64;
+                let end = end.timestamp_millis().div_euclid(interval)
+                    + (end.timestamp_millis().rem_euclid(interval) > 0) as i64;
+                (end - start).max(0) as i32
+            }
+            Self::Cron(schedule) => schedule
+                .after(&(start - TimeDelta::nanoseconds(1)))
+                .take_while(|v| *v < end)
+                .count() as i32,
+        }
+    }
+
+    /// Get next expected beat after given time
+    pub fn next_after(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Self::Interval(interval) => Some(time + TimeDelta::from_std(*interval).ok()?),
+            Self::Cron(schedule) => schedule.after(&time).next(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn cron_fields() {
+        let start = Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap();
+        let end = start + TimeDelta::hours(1);
+
+        let five = Schedule::cron("*/15 * * * *").unwrap();
+        let six = Schedule::cron("0 */15 * * * *").unwrap();
+        assert_eq!(five.count(start, end), 4);
+        assert_eq!(six.count(start, end), 4);
+        assert_eq!(five.next_after(start), Some(start + TimeDelta::minutes(15)));
+
+        assert!(Schedule::cron("* * * *").is_err());
+    }
+
+    #[test]
+    fn cron_utc() {
+        let start = Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap();
+        let daily = Schedule::cron("30 2 * * *").unwrap();
+        assert_eq!(
+            daily.next_after(start),
+            Some(Utc.with_ymd_and_hms(2025, 3, 1, 2, 30, 0).unwrap())
+        );
+    }
+}
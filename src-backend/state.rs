@@ -0,0 +1,148 @@
+use std::{
+    collections::HashMap,
+    sync::{OnceLock, RwLock},
+};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use crate::config::Config;
+
+/// Service state
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum State {
+    Up,
+    Degraded,
+    Down,
+    #[default]
+    Unknown,
+    Paused,
+}
+
+impl State {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Up => "up",
+            Self::Degraded => "degraded",
+            Self::Down => "down",
+            Self::Unknown => "unknown",
+            Self::Paused => "paused",
+        }
+    }
+
+    /// Check whether state is an incident
+    pub fn is_incident(&self) -> bool {
+        matches!(self, Self::Degraded | Self::Down)
+    }
+}
+
+/// State change of a service
+#[derive(Debug)]
+pub struct Transition {
+    pub key: String,
+    pub from: Option<State>,
+    pub to: State,
+    pub cause: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Latest check result of a service
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Health {
+    pub state: State,
+    pub cause: Option<String>,
+    pub cert_days: Option<i64>,
+    pub cert_verified: Option<bool>,
+}
+
+impl Health {
+    pub fn up() -> Self {
+        Self {
+            state: State::Up,
+            ..Default::default()
+        }
+    }
+
+    pub fn degraded(cause: impl ToString) -> Self {
+        Self {
+            state: State::Degraded,
+            cause: Some(cause.to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn down(cause: impl ToString) -> Self {
+        Self {
+            state: State::Down,
+            cause: Some(cause.to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn unknown(cause: impl ToString) -> Self {
+        Self {
+            state: State::Unknown,
+            cause: Some(cause.to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+fn get_instance() -> &'static RwLock<HashMap<String, Health>> {
+    static I: OnceLock<RwLock<HashMap<String, Health>>> = OnceLock::new();
+    I.get_or_init(Default::default)
+}
+
+/// Get latest health of service by key
+pub fn get(key: &str) -> Health {
+    get_instance()
+        .read()
+        .unwrap()
+        .get(key)
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Update latest health of service by key, paused service stays paused
+pub fn set(key: String, mut health: Health) {
+    if Config::get_instance().is_paused(&key) {
+        health = Health {
+            state: State::Paused,
+            ..Default::default()
+        };
+    }
+
+    // Notify state change
+    let transition = {
+        let mut instance = get_instance().write().unwrap();
+        let from = instance.get(&key).map(|v| v.state);
+        let transition = (from != Some(health.state)).then(|| Transition {
+            key: key.clone(),
+            from,
+            to: health.state,
+            cause: health.cause.clone(),
+            timestamp: Utc::now(),
+        });
+        instance.insert(key, health);
+        transition
+    };
+    if let (Some(transition), Some(sender)) = (transition, get_sender().get()) {
+        let _ = sender.send(transition);
+    }
+}
+
+fn get_sender() -> &'static OnceLock<UnboundedSender<Transition>> {
+    static I: OnceLock<UnboundedSender<Transition>> = OnceLock::new();
+    &I
+}
+
+/// Subscribe state changes of all services, only once
+pub fn subscribe() -> UnboundedReceiver<Transition> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+    get_sender()
+        .set(sender)
+        .expect("state changes already subscribed");
+    receiver
+}
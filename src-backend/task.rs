@@ -1,38 +1,107 @@
-use std::{collections::HashMap, future::Future};
+use std::{collections::HashMap, future::Future, sync::Mutex};
 
-use anyhow::Result;
-use bollard::{container::ListContainersOptions, Docker};
-use chrono::{Duration, Timelike, Utc};
+use anyhow::{Context, Result};
+use bollard::{
+    container::{InspectContainerOptions, ListContainersOptions, StatsOptions},
+    secret::{ContainerSummary, HealthStatusEnum},
+    system::EventsOptions,
+    Docker,
+};
+use chrono::{DateTime, Duration, Utc};
+use futures_util::{future, StreamExt};
 use itertools::Itertools;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, IntoActiveModel,
-    QueryFilter,
+    sea_query::OnConflict, ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection,
+    EntityTrait, IntoActiveModel, QueryFilter, QueryOrder,
+};
+use tokio::{
+    sync::mpsc::UnboundedReceiver,
+    time::{self, MissedTickBehavior},
 };
-use tokio::time;
 use tracing::{debug, error, info};
 
 use crate::{
-    config::{Config, ConfigService},
-    model::{heartbeat, status},
+    config::{Config, ConfigRetention, ConfigService, ConfigView},
+    model::{event, heartbeat, incident, metric, status},
+    probe,
+    state::{self, Health, State, Transition},
+    utils,
 };
 
 pub fn clean_database(db: &DatabaseConnection) -> impl Future<Output = ()> {
     let task_db = db.clone();
 
     async fn wrapper(db: &DatabaseConnection) -> Result<()> {
+        let cfg = Config::get_instance();
+        let services = cfg.get_services();
+
+        let now = Utc::now();
+
+        // Keep raw heartbeats for at least two coarsest buckets
+        let bucket = services.iter().map(|s| cfg.get_bucket(s)).max();
+        let raw = cfg.retention.raw.max(bucket.unwrap_or(cfg.bucket) * 2);
+        let raw = Duration::from_std(raw).unwrap();
+
         // Clean outdated heartbeats
         let result = heartbeat::Entity::delete_many()
-            .filter(heartbeat::Column::Timestamp.lt(Utc::now() - Duration::days(3)))
+            .filter(heartbeat::Column::Timestamp.lt(now - raw))
             .exec(db)
             .await?;
         debug!("heartbeats cleaned: count={}", result.rows_affected);
 
-        // Clean outdated status
+        // Clean outdated rollups, keeping them for windows viewing them
+        let mut count = 0;
+        for resolution in [ConfigRetention::HOURLY, ConfigRetention::DAILY] {
+            let window = services
+                .iter()
+                .filter(|s| cfg.get_bucket(s) == resolution)
+                .map(|s| cfg.get_window(s))
+                .max()
+                .unwrap_or_default();
+            let keep = cfg.retention.get(resolution).unwrap().max(window);
+            let result = status::Entity::delete_many()
+                .filter(status::Column::Resolution.eq(resolution.as_secs() as i64))
+                .filter(status::Column::Timestamp.lt(now - Duration::from_std(keep).unwrap()))
+                .exec(db)
+                .await?;
+            count += result.rows_affected;
+        }
+
+        // Clean outdated status of other resolutions
+        let window = services.iter().map(|s| cfg.get_window(s)).max();
+        let window = Duration::from_std(window.unwrap_or(cfg.window)).unwrap();
         let result = status::Entity::delete_many()
-            .filter(status::Column::Timestamp.lt(Utc::now() - Duration::days(3)))
+            .filter(status::Column::Resolution.is_not_in([
+                ConfigRetention::HOURLY.as_secs() as i64,
+                ConfigRetention::DAILY.as_secs() as i64,
+            ]))
+            .filter(status::Column::Timestamp.lt(now - window))
+            .exec(db)
+            .await?;
+        count += result.rows_affected;
+        debug!("status cleaned: count={}", count);
+
+        // Clean outdated events
+        let result = event::Entity::delete_many()
+            .filter(event::Column::Timestamp.lt(now - raw))
+            .exec(db)
+            .await?;
+        debug!("events cleaned: count={}", result.rows_affected);
+
+        // Clean outdated incidents
+        let daily = Duration::from_std(cfg.retention.daily).unwrap();
+        let result = incident::Entity::delete_many()
+            .filter(incident::Column::End.lt(now - daily))
             .exec(db)
             .await?;
-        debug!("status cleaned: count={}", result.rows_affected);
+        debug!("incidents cleaned: count={}", result.rows_affected);
+
+        // Clean outdated metrics
+        let result = metric::Entity::delete_many()
+            .filter(metric::Column::Timestamp.lt(now - raw))
+            .exec(db)
+            .await?;
+        debug!("metrics cleaned: count={}", result.rows_affected);
 
         // Success
         Ok(())
@@ -54,94 +123,314 @@ pub fn clean_database(db: &DatabaseConnection) -> impl Future<Output = ()> {
     }
 }
 
-pub fn check_containers(db: &DatabaseConnection, docker: &Docker) -> impl Future<Output = ()> {
+/// Interval of container checks, also the expected beat schedule of docker services
+pub const CHECK_CONTAINERS_INTERVAL: std::time::Duration = std::time::Duration::from_secs(300);
+
+pub fn check_containers(
+    db: &DatabaseConnection,
+    dockers: &HashMap<String, Docker>,
+) -> impl Future<Output = ()> {
     let task_db = db.clone();
-    let task_docker = docker.clone();
+    let task_dockers = dockers.clone();
 
-    async fn wrapper(db: &DatabaseConnection, docker: &Docker) -> Result<()> {
+    async fn wrapper(
+        db: &DatabaseConnection,
+        dockers: &HashMap<String, Docker>,
+        starts: &Mutex<HashMap<String, (i64, String)>>,
+    ) -> Result<()> {
         let cfg = Config::get_instance();
 
-        // Get container list
-        let result = docker
-            .list_containers(Some(ListContainersOptions::<String> {
-                all: true,
-                ..Default::default()
-            }))
-            .await?
-            .into_iter()
-            .filter(|r| r.names.is_some() && r.state.is_some());
-
-        // For named containers
-        for r in result {
-            // Extract name
-            let names = r.names.as_ref().unwrap();
-            if names.is_empty() {
-                continue;
-            }
-            let name = &names[0];
+        // Get container lists concurrently
+        let results = future::join_all(dockers.iter().map(|(endpoint, docker)| async move {
+            let result = docker
+                .list_containers(Some(ListContainersOptions::<String> {
+                    all: true,
+                    ..Default::default()
+                }))
+                .await;
+            (endpoint, docker, result)
+        }))
+        .await;
 
-            // Find service name
-            let service = cfg.services.iter().find(|s| {
-                if let ConfigService::Docker { container, .. } = s {
-                    container == name
-                } else {
-                    false
+        let mut lists = vec![];
+        for (endpoint, docker, result) in results {
+            // Report unreachable endpoint
+            let result = match result {
+                Ok(result) => result,
+                Err(err) => {
+                    error!("docker endpoint fail: name={}, err={}", endpoint, err);
+                    for s in cfg.get_services() {
+                        if s.get_endpoint() == Some(endpoint) {
+                            state::set(
+                                s.get_key(),
+                                Health::unknown(format!("docker `{}` unreachable", endpoint)),
+                            );
+                        }
+                    }
+                    continue;
+                }
+            };
+            let result = result
+                .into_iter()
+                .filter(|r| r.names.is_some() && r.state.is_some())
+                .collect::<Vec<_>>();
+
+            // Discover labeled containers
+            let discovered = result
+                .iter()
+                .filter_map(|r| {
+                    let labels = r.labels.as_ref()?;
+                    if labels.get("celestob.enable").map(String::as_str) != Some("true") {
+                        return None;
+                    }
+                    let container = r.names.as_ref()?.first()?;
+
+                    Some(ConfigService::Docker {
+                        name: labels
+                            .get("celestob.name")
+                            .cloned()
+                            .unwrap_or_else(|| container.trim_start_matches('/').to_string()),
+                        description: labels
+                            .get("celestob.description")
+                            .cloned()
+                            .unwrap_or_default(),
+                        container: container.clone(),
+                        view: ConfigView {
+                            paused: labels.get("celestob.paused").map(String::as_str)
+                                == Some("true"),
+                            ..Default::default()
+                        },
+                        endpoint: endpoint.clone(),
+                        healthcheck: labels.get("celestob.healthcheck").map(String::as_str)
+                            != Some("false"),
+                    })
+                })
+                .collect::<Vec<_>>();
+            cfg.set_discovered(endpoint, discovered);
+
+            lists.push((endpoint, docker, result));
+        }
+        let services = cfg.get_services();
+
+        // Check each endpoint concurrently
+        future::join_all(lists.into_iter().map(|(endpoint, docker, result)| {
+            let services = &services;
+            async move {
+                for r in result {
+                    // Extract name
+                    let names = r.names.as_ref().unwrap();
+                    if names.is_empty() {
+                        continue;
+                    }
+                    let name = &names[0];
+
+                    // Find service name
+                    let service = services.iter().find(|s| {
+                        if let ConfigService::Docker { container, .. } = s {
+                            container == name && s.get_endpoint() == Some(endpoint)
+                        } else {
+                            false
+                        }
+                    });
+                    let Some(service) = service else {
+                        continue;
+                    };
+
+                    // Report failure and continue with next container
+                    if let Err(err) = check_container(db, docker, service, r, starts).await {
+                        error!(
+                            "container check fail: name={}, err={}",
+                            service.get_key(),
+                            err
+                        );
+                    }
                 }
-            });
-            if service.is_none() {
-                continue;
             }
-            let name = service.unwrap().get_name();
-
-            // Check state
-            if r.state.as_ref().unwrap() != "running" {
-                debug!(
-                    "container down: name={}, state={}",
-                    name,
-                    r.state.as_ref().unwrap()
+        }))
+        .await;
+
+        // Success
+        Ok(())
+    }
+
+    // Subroutine for checking single container
+    async fn check_container(
+        db: &DatabaseConnection,
+        docker: &Docker,
+        service: &ConfigService,
+        r: ContainerSummary,
+        starts: &Mutex<HashMap<String, (i64, String)>>,
+    ) -> Result<()> {
+        let name = service.get_name();
+
+        // Check state
+        if r.state.as_ref().unwrap() != "running" {
+            debug!(
+                "container down: name={}, state={}",
+                name,
+                r.state.as_ref().unwrap()
+            );
+            state::set(
+                service.get_key(),
+                Health::down(format!("container {}", r.state.as_ref().unwrap())),
+            );
+            return Ok(());
+        }
+
+        // Inspect container
+        let Some(id) = &r.id else {
+            return Ok(());
+        };
+        let inspect = docker
+            .inspect_container(id, None::<InspectContainerOptions>)
+            .await?;
+        let mut health = Health::up();
+
+        // Check restarts since last check
+        let restart_count = inspect.restart_count.unwrap_or_default();
+        let started_at = inspect
+            .state
+            .as_ref()
+            .and_then(|v| v.started_at.clone())
+            .unwrap_or_default();
+        let last = starts
+            .lock()
+            .unwrap()
+            .insert(service.get_key(), (restart_count, started_at.clone()));
+        if let Some((last_count, last_started_at)) = last {
+            if restart_count != last_count || started_at != last_started_at {
+                let count = (restart_count - last_count).max(1);
+                debug!("container restarted: name={}, count={}", name, count);
+                state::set(
+                    service.get_key(),
+                    Health::degraded(format!("container restarted {} time(s)", count)),
                 );
-                continue;
+
+                let event = event::ActiveModel {
+                    name: ActiveValue::Set(service.get_key()),
+                    kind: ActiveValue::Set("restart".into()),
+                    message: ActiveValue::Set(Some(format!(
+                        "restarted {} time(s) since last check, started at {}",
+                        count, started_at
+                    ))),
+                    ..Default::default()
+                };
+                event::Entity::insert(event).exec(db).await?;
+                return Ok(());
             }
+        }
 
-            // Create heartbeat
-            let heartbeat = heartbeat::ActiveModel {
-                name: ActiveValue::Set(format!("docker:{}", name)),
-                ..Default::default()
-            };
-            heartbeat::Entity::insert(heartbeat).exec(db).await?;
+        // Check health status
+        let check = inspect.state.and_then(|v| v.health);
+        if let ConfigService::Docker {
+            healthcheck: true, ..
+        } = service
+        {
+            let status = check.as_ref().and_then(|v| v.status);
+            match status {
+                Some(HealthStatusEnum::UNHEALTHY) => {
+                    debug!("container down: name={}, health=unhealthy", name);
+                    state::set(service.get_key(), Health::down("container unhealthy"));
+                    return Ok(());
+                }
+                Some(HealthStatusEnum::STARTING) => {
+                    health = Health::degraded("container starting");
+                }
+                _ => {}
+            }
+        }
+        state::set(service.get_key(), health);
+
+        // Take latency from last healthcheck
+        let duration = check
+            .and_then(|v| v.log)
+            .and_then(|v| v.into_iter().last())
+            .and_then(|v| {
+                let start = DateTime::parse_from_rfc3339(&v.start?).ok()?;
+                let end = DateTime::parse_from_rfc3339(&v.end?).ok()?;
+                Some((end - start).num_milliseconds())
+            });
+
+        // Create heartbeat
+        let heartbeat = heartbeat::ActiveModel {
+            name: ActiveValue::Set(service.get_key()),
+            duration: ActiveValue::Set(duration),
+            ..Default::default()
+        };
+        utils::save_heartbeats(db, vec![heartbeat]).await?;
+
+        // Sample resource usage
+        match sample_stats(docker, id).await {
+            Ok(mut metric) => {
+                metric.name = ActiveValue::Set(service.get_key());
+                metric::Entity::insert(metric).exec(db).await?;
+            }
+            Err(err) => debug!("container stats fail: name={}, err={}", name, err),
         }
 
         // Success
         Ok(())
     }
 
+    // Subroutine for sampling container stats
+    async fn sample_stats(docker: &Docker, id: &str) -> Result<metric::ActiveModel> {
+        let stats = docker
+            .stats(
+                id,
+                Some(StatsOptions {
+                    stream: false,
+                    one_shot: false,
+                }),
+            )
+            .next()
+            .await
+            .context("no stats returned")??;
+
+        // CPU percentage
+        let cpu_delta = stats.cpu_stats.cpu_usage.total_usage as f64
+            - stats.precpu_stats.cpu_usage.total_usage as f64;
+        let system_delta = stats.cpu_stats.system_cpu_usage.unwrap_or_default() as f64
+            - stats.precpu_stats.system_cpu_usage.unwrap_or_default() as f64;
+        let cpus = stats.cpu_stats.online_cpus.unwrap_or(1) as f64;
+        let cpu = if cpu_delta > 0.0 && system_delta > 0.0 {
+            cpu_delta / system_delta * cpus * 100.0
+        } else {
+            0.0
+        };
+
+        // Network I/O
+        let (network_rx, network_tx) = stats
+            .networks
+            .unwrap_or_default()
+            .values()
+            .fold((0, 0), |(rx, tx), v| (rx + v.rx_bytes, tx + v.tx_bytes));
+
+        // Success
+        Ok(metric::ActiveModel {
+            cpu: ActiveValue::Set(cpu),
+            memory_usage: ActiveValue::Set(stats.memory_stats.usage.unwrap_or_default() as i64),
+            memory_limit: ActiveValue::Set(stats.memory_stats.limit.unwrap_or_default() as i64),
+            network_rx: ActiveValue::Set(network_rx as i64),
+            network_tx: ActiveValue::Set(network_tx as i64),
+            ..Default::default()
+        })
+    }
+
     async move {
+        // <service name, (restart count, started at)>
+        let starts = Mutex::new(HashMap::new());
+
         loop {
-            // Crontab: */5 * * * * *
+            // Align to check interval
             let now = Utc::now();
-            let next_minute = now.minute() / 5 * 5 + 5;
-            let next_time = if next_minute >= 60 {
-                now.with_minute(next_minute - 60)
-                    .unwrap()
-                    .with_second(0)
-                    .unwrap()
-                    .with_nanosecond(0)
-                    .unwrap()
-                    + Duration::hours(1)
-            } else {
-                now.with_minute(next_minute)
-                    .unwrap()
-                    .with_second(0)
-                    .unwrap()
-                    .with_nanosecond(0)
-                    .unwrap()
-            };
+            let next_time = utils::truncate(&now, CHECK_CONTAINERS_INTERVAL)
+                + Duration::from_std(CHECK_CONTAINERS_INTERVAL).unwrap();
             let delta = next_time - now;
             time::sleep(delta.to_std().unwrap()).await;
 
             // Execute task
             info!("execute task: check containers");
-            if let Err(err) = wrapper(&task_db, &task_docker).await {
+            if let Err(err) = wrapper(&task_db, &task_dockers, &starts).await {
                 error!("task fail: name=check containers, err={}", err);
             } else {
                 info!("task success: name=check containers");
@@ -150,113 +439,467 @@ pub fn check_containers(db: &DatabaseConnection, docker: &Docker) -> impl Future
     }
 }
 
-pub fn collect_status(db: &DatabaseConnection) -> impl Future<Output = ()> {
+pub fn watch_containers(
+    db: &DatabaseConnection,
+    endpoint: &str,
+    docker: &Docker,
+) -> impl Future<Output = ()> {
     let task_db = db.clone();
+    let task_endpoint = endpoint.to_string();
+    let task_docker = docker.clone();
 
-    async fn wrapper(db: &DatabaseConnection) -> Result<()> {
+    async fn wrapper(db: &DatabaseConnection, endpoint: &str, docker: &Docker) -> Result<()> {
         let cfg = Config::get_instance();
-        let now = Utc::now();
 
-        // <service name, [(timestamp, count)]>
-        let status = heartbeat::Entity::find()
-            .filter(heartbeat::Column::Timestamp.gte(now - Duration::hours(3)))
-            .all(db)
-            .await?
-            .into_iter()
-            .into_group_map_by(|v| v.name.clone())
-            .into_iter()
-            .map(|(k, v)| {
+        // Subscribe container events
+        let mut stream = docker.events(Some(EventsOptions::<String> {
+            filters: HashMap::from([
+                ("type".into(), vec!["container".into()]),
                 (
-                    k,
-                    v.into_iter()
-                        .into_group_map_by(|v| {
-                            v.timestamp.format("%Y-%m-%dT%H:00:00%:z").to_string()
-                        })
-                        .into_iter()
-                        .k_largest_by(3, |a, b| a.0.cmp(&b.0))
-                        .map(|(k, v)| (k, v.len()))
-                        .collect::<HashMap<_, _>>(),
-                )
-            })
-            .collect::<HashMap<_, _>>();
-
-        // Activate timestamps
-        let tms = vec![
-            now.format("%Y-%m-%dT%H:00:00%:z").to_string(),
-            (now - Duration::hours(1))
-                .format("%Y-%m-%dT%H:00:00%:z")
-                .to_string(),
-        ];
-
-        // Subroutine for insert/update status
-        async fn save_status(
-            db: &DatabaseConnection,
-            name: &String,
-            timestamp: &String,
-            count: i32,
-        ) -> Result<()> {
-            // Try fetch status
-            let status = status::Entity::find()
-                .filter(status::Column::Name.eq(name))
-                .filter(status::Column::Timestamp.eq(timestamp))
-                .one(db)
-                .await?;
+                    "event".into(),
+                    vec![
+                        "die".into(),
+                        "start".into(),
+                        "health_status".into(),
+                        "oom".into(),
+                    ],
+                ),
+            ]),
+            ..Default::default()
+        }));
 
-            // Insert new or update old
-            match status {
-                Some(status) => {
-                    let mut status = status.into_active_model();
-                    status.count = ActiveValue::Set(count);
-                    status.update(db).await?;
+        while let Some(msg) = stream.next().await {
+            let msg = msg?;
+
+            // Extract name and action
+            let Some(action) = msg.action else {
+                continue;
+            };
+            let Some(name) = msg
+                .actor
+                .and_then(|v| v.attributes)
+                .and_then(|mut v| v.remove("name"))
+            else {
+                continue;
+            };
+            let name = format!("/{}", name);
+
+            // Find service
+            let service = cfg.get_services().into_iter().find(|s| {
+                if let ConfigService::Docker { container, .. } = s {
+                    *container == name && s.get_endpoint().map(String::as_str) == Some(endpoint)
+                } else {
+                    false
                 }
-                None => {
-                    let status = status::ActiveModel {
-                        timestamp: ActiveValue::Set(timestamp.clone()),
-                        name: ActiveValue::Set(name.clone()),
-                        count: ActiveValue::Set(count),
-                        ..Default::default()
-                    };
-                    status.insert(db).await?;
+            });
+            let Some(service) = service else {
+                continue;
+            };
+            let healthcheck = matches!(
+                service,
+                ConfigService::Docker {
+                    healthcheck: true,
+                    ..
                 }
+            );
+
+            // Transit state
+            let (kind, message) = action.split_once(": ").unwrap_or((&action, ""));
+            match kind {
+                "die" => state::set(service.get_key(), Health::down("container died")),
+                "oom" => state::set(service.get_key(), Health::down("container out of memory")),
+                "start" => state::set(service.get_key(), Health::up()),
+                "health_status" if healthcheck => match message {
+                    "healthy" => state::set(service.get_key(), Health::up()),
+                    "unhealthy" => {
+                        state::set(service.get_key(), Health::down("container unhealthy"))
+                    }
+                    _ => state::set(service.get_key(), Health::degraded("container starting")),
+                },
+                _ => continue,
             }
+            debug!(
+                "container event: name={}, action={}",
+                service.get_name(),
+                action
+            );
 
-            // Success
-            Ok(())
+            // Record event
+            let event = event::ActiveModel {
+                timestamp: match msg.time_nano {
+                    Some(nano) => ActiveValue::Set(DateTime::from_timestamp_nanos(nano)),
+                    None => ActiveValue::Set(Utc::now()),
+                },
+                name: ActiveValue::Set(service.get_key()),
+                kind: ActiveValue::Set(kind.into()),
+                message: ActiveValue::Set((!message.is_empty()).then(|| message.into())),
+                ..Default::default()
+            };
+            event::Entity::insert(event).exec(db).await?;
         }
 
-        // For each service
+        // Success
+        Ok(())
+    }
+
+    async move {
+        loop {
+            // Execute task
+            info!("execute task: watch containers, endpoint={}", task_endpoint);
+            if let Err(err) = wrapper(&task_db, &task_endpoint, &task_docker).await {
+                error!(
+                    "task fail: name=watch containers, endpoint={}, err={}",
+                    task_endpoint, err
+                );
+            } else {
+                info!(
+                    "task success: name=watch containers, endpoint={}",
+                    task_endpoint
+                );
+            }
+
+            // Reconnect later
+            time::sleep(Duration::seconds(10).to_std().unwrap()).await;
+        }
+    }
+}
+
+pub fn check_probe(
+    db: &DatabaseConnection,
+    service: &'static ConfigService,
+) -> impl Future<Output = ()> {
+    let task_db = db.clone();
+
+    async fn wrapper(db: &DatabaseConnection, service: &ConfigService) -> Result<()> {
+        // Probe service
+        let start = Utc::now();
+        let health = probe::check(service).await;
+        let duration = (Utc::now() - start).num_milliseconds();
+        let down = health.state == State::Down;
+        if let Some(cause) = &health.cause {
+            debug!(
+                "probe {:?}: name={}, cause={}",
+                health.state,
+                service.get_key(),
+                cause
+            );
+        }
+        state::set(service.get_key(), health);
+        if down {
+            return Ok(());
+        }
+
+        // Create heartbeat
+        let heartbeat = heartbeat::ActiveModel {
+            name: ActiveValue::Set(service.get_key()),
+            duration: ActiveValue::Set(Some(duration)),
+            ..Default::default()
+        };
+        utils::save_heartbeats(db, vec![heartbeat]).await?;
+
+        // Success
+        Ok(())
+    }
+
+    async move {
+        let Some(interval) = service.get_interval() else {
+            return;
+        };
+
+        let mut interval = time::interval(interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+
+            // Execute task
+            debug!("execute task: check probe, service={}", service.get_key());
+            if let Err(err) = wrapper(&task_db, service).await {
+                error!(
+                    "task fail: name=check probe, service={}, err={}",
+                    service.get_key(),
+                    err
+                );
+            }
+        }
+    }
+}
+
+pub fn check_deadlines(db: &DatabaseConnection) -> impl Future<Output = ()> {
+    let task_db = db.clone();
+
+    async fn wrapper(db: &DatabaseConnection, since: DateTime<Utc>) -> Result<()> {
+        let cfg = Config::get_instance();
+        let now = Utc::now();
+
         for s in &cfg.services {
-            let name = if let ConfigService::Http { .. } = s {
-                format!("http:{}", s.get_name())
+            let ConfigService::Http { grace, .. } = s else {
+                continue;
+            };
+            let Some(schedule) = s.get_schedule() else {
+                continue;
+            };
+
+            // Fetch last heartbeat
+            let last = heartbeat::Entity::find()
+                .filter(heartbeat::Column::Name.eq(s.get_key()))
+                .order_by_desc(heartbeat::Column::Timestamp)
+                .one(db)
+                .await?;
+
+            // Compute deadline
+            let last_time = last.as_ref().map_or(since, |v| v.timestamp.max(since));
+            let Some(expected) = schedule.next_after(last_time) else {
+                continue;
+            };
+            let deadline = expected + Duration::from_std(*grace)?;
+
+            // Update state
+            let health = if now > deadline {
+                Health::down(format!(
+                    "heartbeat missed, expected at {}",
+                    expected.to_rfc3339()
+                ))
+            } else if now > expected {
+                Health::degraded(format!(
+                    "heartbeat late, expected at {}",
+                    expected.to_rfc3339()
+                ))
             } else {
-                format!("docker:{}", s.get_name())
+                match last {
+                    Some(v) if !v.success => {
+                        Health::down(v.message.unwrap_or_else(|| "job failed".into()))
+                    }
+                    Some(_) => Health::up(),
+                    None => continue,
+                }
+            };
+            state::set(s.get_key(), health);
+        }
+
+        // Success
+        Ok(())
+    }
+
+    async move {
+        let since = Utc::now();
+
+        loop {
+            // Execute task
+            debug!("execute task: check deadlines");
+            if let Err(err) = wrapper(&task_db, since).await {
+                error!("task fail: name=check deadlines, err={}", err);
+            }
+
+            // Wait until next time
+            time::sleep(Duration::minutes(1).to_std().unwrap()).await;
+        }
+    }
+}
+
+pub fn record_incidents(
+    db: &DatabaseConnection,
+    mut receiver: UnboundedReceiver<Transition>,
+) -> impl Future<Output = ()> {
+    let task_db = db.clone();
+
+    async fn wrapper(db: &DatabaseConnection, transition: Transition) -> Result<()> {
+        // Unknown state neither opens nor resolves incidents
+        if transition.to == State::Unknown {
+            return Ok(());
+        }
+
+        // Resolve open incidents of other states
+        let incidents = incident::Entity::find()
+            .filter(incident::Column::Name.eq(&transition.key))
+            .filter(incident::Column::End.is_null())
+            .all(db)
+            .await?;
+        let mut ongoing = false;
+        for v in incidents {
+            if v.state == transition.to.as_str() {
+                ongoing = true;
+                continue;
+            }
+
+            let duration = (transition.timestamp - v.start).num_milliseconds();
+            let mut v = v.into_active_model();
+            v.end = ActiveValue::Set(Some(transition.timestamp));
+            v.duration = ActiveValue::Set(Some(duration));
+            v.update(db).await?;
+        }
+
+        // Open new incident
+        if transition.to.is_incident() && !ongoing {
+            let incident = incident::ActiveModel {
+                name: ActiveValue::Set(transition.key),
+                state: ActiveValue::Set(transition.to.as_str().into()),
+                cause: ActiveValue::Set(transition.cause),
+                start: ActiveValue::Set(transition.timestamp),
+                ..Default::default()
             };
+            incident::Entity::insert(incident).exec(db).await?;
+        }
+
+        // Success
+        Ok(())
+    }
 
-            for timestamp in &tms {
-                let count = status
-                    .get(&name)
-                    .map(|status| status.get(timestamp).unwrap_or(&0))
-                    .unwrap_or(&0);
-                save_status(db, &name, timestamp, *count as i32).await?;
+    async move {
+        while let Some(transition) = receiver.recv().await {
+            // Execute task
+            debug!(
+                "state changed: name={}, from={:?}, to={:?}",
+                transition.key, transition.from, transition.to
+            );
+            if let Err(err) = wrapper(&task_db, transition).await {
+                error!("task fail: name=record incidents, err={}", err);
             }
         }
+    }
+}
+
+pub fn collect_status(db: &DatabaseConnection) -> impl Future<Output = ()> {
+    let task_db = db.clone();
+
+    async fn wrapper(
+        db: &DatabaseConnection,
+        opened: &mut HashMap<std::time::Duration, DateTime<Utc>>,
+        closed: &mut HashMap<std::time::Duration, DateTime<Utc>>,
+    ) -> Result<()> {
+        let cfg = Config::get_instance();
+        let now = Utc::now();
+        let services = cfg.get_services();
+
+        // <(resolution, [timestamp]), [service name]>
+        let mut jobs = HashMap::<_, Vec<_>>::new();
+        let mut opening = HashMap::new();
+        let mut closing = HashMap::new();
+        for s in &services {
+            let resolutions = cfg.get_resolutions(s);
+
+            // Buckets of finest resolution since the one open at last run
+            let bucket = resolutions[0];
+            let step = Duration::from_std(bucket)?;
+            let current = utils::truncate(&now, bucket);
+            let start = opened
+                .get(&bucket)
+                .copied()
+                .unwrap_or(current - step)
+                .max(utils::truncate(&(now - cfg.retention.raw), bucket));
+            let tms = (0..)
+                .map(|i| start + step * i)
+                .take_while(|v| *v <= current)
+                .collect::<Vec<_>>();
+            jobs.entry((bucket, tms)).or_default().push(s.get_key());
+            opening.insert(bucket, current);
+
+            // Rollups only once their bucket closes
+            for &resolution in &resolutions[1..] {
+                let previous = utils::truncate(&now, resolution) - Duration::from_std(resolution)?;
+                if closed.get(&resolution) != Some(&previous) {
+                    jobs.entry((resolution, vec![previous]))
+                        .or_default()
+                        .push(s.get_key());
+                    closing.insert(resolution, previous);
+                }
+            }
+        }
+
+        let mut rows = vec![];
+        for ((bucket, tms), names) in jobs {
+            let start = tms[0];
+            let end = tms[tms.len() - 1] + Duration::from_std(bucket)?;
+
+            // <(service name, timestamp), [duration]>
+            let groups = heartbeat::Entity::find()
+                .filter(heartbeat::Column::Name.is_in(names.clone()))
+                .filter(heartbeat::Column::Timestamp.gte(start))
+                .filter(heartbeat::Column::Timestamp.lt(end))
+                .filter(heartbeat::Column::Success.eq(true))
+                .all(db)
+                .await?
+                .into_iter()
+                .map(|v| ((v.name, utils::truncate(&v.timestamp, bucket)), v.duration))
+                .into_group_map();
+
+            // Activate timestamps
+            for name in names {
+                for &timestamp in &tms {
+                    let durations = groups
+                        .get(&(name.clone(), timestamp))
+                        .map_or(&[][..], Vec::as_slice);
+                    let latency = utils::summarize_latency(durations);
+                    rows.push(status::ActiveModel {
+                        timestamp: ActiveValue::Set(timestamp),
+                        name: ActiveValue::Set(name.clone()),
+                        count: ActiveValue::Set(durations.len() as i32),
+                        resolution: ActiveValue::Set(bucket.as_secs() as i64),
+                        latency_min: ActiveValue::Set(latency.map(|v| v[0])),
+                        latency_avg: ActiveValue::Set(latency.map(|v| v[1])),
+                        latency_p50: ActiveValue::Set(latency.map(|v| v[2])),
+                        latency_p95: ActiveValue::Set(latency.map(|v| v[3])),
+                        latency_p99: ActiveValue::Set(latency.map(|v| v[4])),
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+
+        // Overwrite counters drifted from heartbeats
+        for chunk in rows.chunks(500) {
+            status::Entity::insert_many(chunk.to_vec())
+                .on_conflict(
+                    OnConflict::columns([
+                        status::Column::Name,
+                        status::Column::Resolution,
+                        status::Column::Timestamp,
+                    ])
+                    .update_columns([
+                        status::Column::Count,
+                        status::Column::LatencyMin,
+                        status::Column::LatencyAvg,
+                        status::Column::LatencyP50,
+                        status::Column::LatencyP95,
+                        status::Column::LatencyP99,
+                    ])
+                    .to_owned(),
+                )
+                .exec_without_returning(db)
+                .await?;
+        }
+        opened.extend(opening);
+        closed.extend(closing);
 
         // Success
         Ok(())
     }
 
     async move {
+        // <finest resolution, bucket open at last run>
+        let mut opened = HashMap::new();
+
+        // <rollup resolution, last reconciled bucket>
+        let mut closed = HashMap::new();
+
         loop {
             // Execute task
             info!("execute task: collect status");
-            if let Err(err) = wrapper(&task_db).await {
+            if let Err(err) = wrapper(&task_db, &mut opened, &mut closed).await {
                 error!("task fail: name=collect status, err={}", err);
             } else {
                 info!("task success: name=collect status");
             }
 
-            // Wait until next time
-            time::sleep(Duration::minutes(4).to_std().unwrap()).await;
+            // Wait until next time, at least once per smallest bucket
+            let cfg = Config::get_instance();
+            let interval = cfg
+                .get_services()
+                .iter()
+                .map(|s| cfg.get_bucket(s))
+                .chain([Duration::minutes(10).to_std().unwrap()])
+                .min()
+                .unwrap();
+            time::sleep(interval).await;
         }
     }
 }
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sea_orm::DatabaseConnection;
+use sha2::Sha256;
+use tokio::net::UdpSocket;
+use tracing::{debug, info};
+
+use crate::{config::Config, utils};
+
+/// Serve UDP heartbeats
+///
+/// Each datagram carries `<token>`, or `<token>:<timestamp>:<signature>` when
+/// `udp_secret` is set, where signature is the hex encoded HMAC-SHA256 of
+/// `<token>:<timestamp>`
+pub async fn serve(db: DatabaseConnection, socket: UdpSocket) {
+    // <token, last accepted timestamp>
+    let mut seen = HashMap::new();
+    let mut buf = [0u8; 512];
+
+    info!(
+        "start listening on `udp://{}`",
+        socket.local_addr().unwrap()
+    );
+    loop {
+        let (len, addr) = match socket.recv_from(&mut buf).await {
+            Ok(v) => v,
+            Err(err) => {
+                debug!("udp receive fail: err={}", err);
+                continue;
+            }
+        };
+
+        if let Err(err) = handle(&db, &buf[..len], &mut seen).await {
+            debug!("udp heartbeat rejected: addr={}, err={}", addr, err);
+        }
+    }
+}
+
+async fn handle(
+    db: &DatabaseConnection,
+    datagram: &[u8],
+    seen: &mut HashMap<String, i64>,
+) -> Result<()> {
+    let cfg = Config::get_instance();
+    let datagram = std::str::from_utf8(datagram)?.trim();
+
+    // Verify signature
+    let token = match &cfg.udp_secret {
+        Some(secret) => {
+            let mut parts = datagram.splitn(3, ':');
+            let (Some(token), Some(timestamp), Some(signature)) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                bail!("signature required");
+            };
+
+            let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())?;
+            mac.update(format!("{}:{}", token, timestamp).as_bytes());
+            mac.verify_slice(&hex::decode(signature)?)
+                .ok()
+                .context("signature mismatch")?;
+
+            // Prevent replay
+            let timestamp = timestamp.parse::<i64>()?;
+            if (Utc::now().timestamp() - timestamp).abs() > cfg.udp_window.as_secs() as i64 {
+                bail!("timestamp out of window");
+            }
+            if seen.get(token).is_some_and(|v| *v >= timestamp) {
+                bail!("timestamp replayed");
+            }
+            seen.insert(token.to_string(), timestamp);
+
+            token
+        }
+        None => datagram,
+    };
+
+    // Get service by token
+    let service = cfg.find_by_token(token).context("token not found")?;
+
+    // Create heartbeat
+    let heartbeat = utils::create_heartbeat(service, true, None, None);
+    utils::save_heartbeats(db, vec![heartbeat]).await?;
+    utils::set_heartbeat_state(service, true, None);
+
+    // Success
+    Ok(())
+}
@@ -0,0 +1,231 @@
+use std::{collections::HashMap, slice};
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use itertools::Itertools;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use serde::Serialize;
+
+use crate::{
+    config::{Config, ConfigService},
+    model::status,
+    schedule::Schedule,
+    utils,
+};
+
+/// Uptime percentages over fixed periods until now
+#[derive(Serialize)]
+pub struct Periods {
+    #[serde(rename = "24h")]
+    pub day: Option<f64>,
+
+    #[serde(rename = "7d")]
+    pub week: Option<f64>,
+
+    #[serde(rename = "30d")]
+    pub month: Option<f64>,
+
+    #[serde(rename = "90d")]
+    pub quarter: Option<f64>,
+}
+
+/// Period lengths in days
+const PERIODS: [i64; 4] = [1, 7, 30, 90];
+
+/// Compute uptime percentages of service over fixed periods
+pub async fn periods(db: &DatabaseConnection, service: &ConfigService) -> Result<Periods> {
+    let mut periods = periods_of(db, slice::from_ref(service)).await?;
+    Ok(periods.remove(0))
+}
+
+/// Compute uptime percentages of services over fixed periods, one query per resolution
+pub async fn periods_of(
+    db: &DatabaseConnection,
+    services: &[ConfigService],
+) -> Result<Vec<Periods>> {
+    let cfg = Config::get_instance();
+    let now = Utc::now();
+    let froms = PERIODS.map(|v| now - Duration::days(v));
+    let resolutions = froms.map(|v| cfg.retention.pick(&v));
+
+    // <(resolution, name), rows>
+    let mut rows = HashMap::<_, Vec<_>>::new();
+    let keys = services
+        .iter()
+        .map(ConfigService::get_key)
+        .collect::<Vec<_>>();
+    for resolution in resolutions.iter().unique() {
+        let from = froms
+            .iter()
+            .zip(&resolutions)
+            .filter(|(_, v)| *v == resolution)
+            .map(|(v, _)| *v)
+            .min()
+            .unwrap();
+        let result = status::Entity::find()
+            .filter(status::Column::Name.is_in(keys.clone()))
+            .filter(status::Column::Resolution.eq(resolution.as_secs() as i64))
+            .filter(status::Column::Timestamp.gte(utils::truncate(&from, *resolution)))
+            .filter(status::Column::Timestamp.lt(now))
+            .all(db)
+            .await?;
+        for v in result {
+            rows.entry((*resolution, v.name.clone()))
+                .or_default()
+                .push(v);
+        }
+    }
+
+    // Tally each period
+    let mut ret = vec![];
+    for s in services {
+        let schedule = s.get_schedule();
+        let mut uptime = [None; 4];
+        for (i, (from, resolution)) in froms.iter().zip(&resolutions).enumerate() {
+            let (Some(schedule), Some(rows)) = (&schedule, rows.get(&(*resolution, s.get_key())))
+            else {
+                continue;
+            };
+            let bucket = Duration::from_std(*resolution)?;
+            uptime[i] = tally(schedule, rows, bucket, *from, now, now);
+        }
+        let [day, week, month, quarter] = uptime;
+        ret.push(Periods {
+            day,
+            week,
+            month,
+            quarter,
+        });
+    }
+
+    // Success
+    Ok(ret)
+}
+
+/// Compute uptime percentage of service in `[from, to)`, `None` if nothing expected
+///
+/// Buckets without status are skipped as the service was not monitored then.
+pub async fn compute(
+    db: &DatabaseConnection,
+    service: &ConfigService,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> Result<Option<f64>> {
+    let cfg = Config::get_instance();
+    let Some(schedule) = service.get_schedule() else {
+        return Ok(None);
+    };
+
+    // Fetch rollups of finest resolution still kept
+    let resolution = cfg.retention.pick(&from);
+    let rows = status::Entity::find()
+        .filter(status::Column::Name.eq(service.get_key()))
+        .filter(status::Column::Resolution.eq(resolution.as_secs() as i64))
+        .filter(status::Column::Timestamp.gte(utils::truncate(&from, resolution)))
+        .filter(status::Column::Timestamp.lt(to))
+        .all(db)
+        .await?;
+
+    // Success
+    Ok(tally(
+        &schedule,
+        &rows,
+        Duration::from_std(resolution)?,
+        from,
+        to,
+        Utc::now(),
+    ))
+}
+
+/// Sum expected and received beats of buckets in `[from, to)`, surplus ones not counted
+///
+/// Received beats of buckets partially in range are prorated by expected beats.
+fn tally(
+    schedule: &Schedule,
+    rows: &[status::Model],
+    bucket: Duration,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Option<f64> {
+    let (mut expected, mut received) = (0, 0.0);
+    for v in rows {
+        let end = (v.timestamp + bucket).min(now);
+        let full = schedule.count(v.timestamp, end);
+        let part = schedule.count(v.timestamp.max(from), end.min(to));
+        if part == 0 {
+            continue;
+        }
+        expected += part;
+        received += v.count.min(full) as f64 * part as f64 / full as f64;
+    }
+    if expected == 0 {
+        return None;
+    }
+
+    Some(received / expected as f64 * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn row(timestamp: DateTime<Utc>, count: i32) -> status::Model {
+        status::Model {
+            uuid: Uuid::new_v4(),
+            timestamp,
+            name: "http:job".into(),
+            count,
+            resolution: 3600,
+            latency_min: None,
+            latency_avg: None,
+            latency_p50: None,
+            latency_p95: None,
+            latency_p99: None,
+        }
+    }
+
+    #[test]
+    fn tally_prorated() {
+        let schedule = Schedule::interval(std::time::Duration::from_secs(300));
+        let time = |h, m| Utc.with_ymd_and_hms(2025, 3, 1, h, m, 0).unwrap();
+        let bucket = Duration::hours(1);
+
+        // Half of first bucket in range, with half of its beats received
+        let rows = [row(time(0, 0), 6), row(time(1, 0), 12)];
+        let uptime = tally(
+            &schedule,
+            &rows,
+            bucket,
+            time(0, 30),
+            time(2, 0),
+            time(3, 0),
+        );
+        assert_eq!(uptime, Some((3.0 + 12.0) / 18.0 * 100.0));
+
+        // Current bucket only expects beats until now
+        let rows = [row(time(2, 0), 6)];
+        let uptime = tally(
+            &schedule,
+            &rows,
+            bucket,
+            time(0, 0),
+            time(3, 0),
+            time(2, 30),
+        );
+        assert_eq!(uptime, Some(100.0));
+
+        // Surplus beats not counted
+        let rows = [row(time(0, 0), 20), row(time(1, 0), 0)];
+        let uptime = tally(&schedule, &rows, bucket, time(0, 0), time(2, 0), time(3, 0));
+        assert_eq!(uptime, Some(50.0));
+
+        assert_eq!(
+            tally(&schedule, &[], bucket, time(0, 0), time(2, 0), time(3, 0)),
+            None
+        );
+    }
+}
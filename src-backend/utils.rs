@@ -1,26 +1,145 @@
+use std::{collections::HashMap, time::Duration};
+
 use anyhow::Result;
-use sea_orm::{ConnectionTrait, DatabaseConnection, EntityTrait, Schema};
+use bollard::{Docker, API_DEFAULT_VERSION};
+use chrono::{DateTime, DurationRound, TimeDelta, Utc};
+use sea_orm::{
+    sea_query::{Expr, OnConflict},
+    ActiveValue, ConnectionTrait, EntityTrait,
+};
+
+use crate::{
+    config::{Config, ConfigDocker, ConfigService},
+    model::{heartbeat, status},
+    state::{self, Health},
+};
+
+/// Create heartbeat of push service
+pub fn create_heartbeat(
+    service: &ConfigService,
+    success: bool,
+    message: Option<String>,
+    duration: Option<i64>,
+) -> heartbeat::ActiveModel {
+    heartbeat::ActiveModel {
+        name: ActiveValue::Set(service.get_key()),
+        success: ActiveValue::Set(success),
+        message: ActiveValue::Set(message),
+        duration: ActiveValue::Set(duration),
+        ..Default::default()
+    }
+}
+
+/// Update service state by saved heartbeat of push service
+pub fn set_heartbeat_state(service: &ConfigService, success: bool, message: Option<&str>) {
+    if success {
+        state::set(service.get_key(), Health::up());
+    } else {
+        let cause = message.unwrap_or("job failed");
+        state::set(service.get_key(), Health::down(cause));
+    }
+}
 
-/// Initialize database table
-pub async fn init_table<E>(db: &DatabaseConnection, entity: E) -> Result<()>
+/// Insert heartbeats and count successful ones into status buckets
+pub async fn save_heartbeats<C>(db: &C, heartbeats: Vec<heartbeat::ActiveModel>) -> Result<()>
 where
-    E: EntityTrait,
+    C: ConnectionTrait,
 {
-    let backend = db.get_database_backend();
-    let schema = Schema::new(backend);
-
-    // Create table
-    let mut stmt = schema.create_table_from_entity(entity);
-    stmt.if_not_exists();
-    db.execute(backend.build(&stmt)).await?;
-
-    // Create indices
-    let mut stmts = schema.create_index_from_entity(entity);
-    for stmt in stmts.iter_mut() {
-        stmt.if_not_exists();
-        db.execute(backend.build(stmt)).await?;
+    let cfg = Config::get_instance();
+    let services = cfg.get_services();
+
+    // <(name, resolution, timestamp), count>
+    let mut counts = HashMap::<_, i32>::new();
+    for v in &heartbeats {
+        let (ActiveValue::Set(name), ActiveValue::Set(timestamp), ActiveValue::Set(true)) =
+            (&v.name, &v.timestamp, &v.success)
+        else {
+            continue;
+        };
+        let Some(service) = services.iter().find(|s| s.get_key() == *name) else {
+            continue;
+        };
+        for bucket in cfg.get_resolutions(service) {
+            let key = (
+                name.clone(),
+                bucket.as_secs() as i64,
+                truncate(timestamp, bucket),
+            );
+            *counts.entry(key).or_default() += 1;
+        }
+    }
+
+    // Insert heartbeats in chunks within bound parameter limits
+    for chunk in heartbeats.chunks(500) {
+        heartbeat::Entity::insert_many(chunk.to_vec())
+            .exec_without_returning(db)
+            .await?;
+    }
+
+    // Increase bucket counters
+    for ((name, resolution, timestamp), count) in counts {
+        let status = status::ActiveModel {
+            timestamp: ActiveValue::Set(timestamp),
+            name: ActiveValue::Set(name),
+            count: ActiveValue::Set(count),
+            resolution: ActiveValue::Set(resolution),
+            ..Default::default()
+        };
+        status::Entity::insert(status)
+            .on_conflict(
+                OnConflict::columns([
+                    status::Column::Name,
+                    status::Column::Resolution,
+                    status::Column::Timestamp,
+                ])
+                .value(
+                    status::Column::Count,
+                    Expr::col((status::Entity, status::Column::Count)).add(count),
+                )
+                .to_owned(),
+            )
+            .exec_without_returning(db)
+            .await?;
     }
 
     // Success
     Ok(())
 }
+
+/// Summarize latencies into `[min, avg, p50, p95, p99]`, `None` if no latency
+pub fn summarize_latency(durations: &[Option<i64>]) -> Option<[i64; 5]> {
+    let mut v = durations.iter().flatten().copied().collect::<Vec<_>>();
+    if v.is_empty() {
+        return None;
+    }
+    v.sort_unstable();
+
+    // Nearest rank percentile
+    let rank = |p: usize| v[(v.len() * p).div_ceil(100) - 1];
+    let avg = v.iter().sum::<i64>() / v.len() as i64;
+    Some([v[0], avg, rank(50), rank(95), rank(99)])
+}
+
+/// Truncate time to the start of its bucket
+pub fn truncate(time: &DateTime<Utc>, bucket: Duration) -> DateTime<Utc> {
+    time.duration_trunc(TimeDelta::from_std(bucket).unwrap())
+        .unwrap()
+}
+
+/// Connect docker endpoint
+pub fn connect_docker(endpoint: &ConfigDocker) -> Result<Docker> {
+    const TIMEOUT: u64 = 120;
+
+    let docker = match (&endpoint.key, &endpoint.cert, &endpoint.ca) {
+        (Some(key), Some(cert), Some(ca)) => {
+            Docker::connect_with_ssl(&endpoint.url, key, cert, ca, TIMEOUT, API_DEFAULT_VERSION)?
+        }
+        _ if endpoint.url.starts_with("unix://") => {
+            Docker::connect_with_unix(&endpoint.url, TIMEOUT, API_DEFAULT_VERSION)?
+        }
+        _ => Docker::connect_with_http(&endpoint.url, TIMEOUT, API_DEFAULT_VERSION)?,
+    };
+
+    // Success
+    Ok(docker)
+}